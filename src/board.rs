@@ -2,57 +2,77 @@ use colored::Colorize;
 
 use crate::game::other_player;
 use crate::game::GameState;
-use crate::moves::PieceMove;
 use crate::notation;
 use crate::pieces;
 use crate::game;
 use crate::moves;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SetupError {
+    InvalidFen,
+    InvalidEpd,
+    MissingKing(game::Player),
+    ExtraKing(game::Player),
+    KingPositionMismatch(game::Player),
+    TooManyPieces(game::Player),
+    TooManyPromotedPieces(game::Player),
+    PawnOnBackRank,
+    OpponentInCheck,
+    InvalidCastlingRights,
+    InvalidEnPassant,
+}
+
+impl std::fmt::Display for SetupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SetupError::InvalidFen => write!(f, "Invalid FEN syntax!"),
+            SetupError::InvalidEpd => write!(f, "Invalid EPD operation syntax!"),
+            SetupError::MissingKing(p) => write!(f, "{p:?} has no king!"),
+            SetupError::ExtraKing(p) => write!(f, "{p:?} has more than one king!"),
+            SetupError::KingPositionMismatch(p) =>
+                write!(f, "The stored {p:?} king position does not match the board!"),
+            SetupError::TooManyPieces(p) => write!(f, "{p:?} has more than 16 pieces!"),
+            SetupError::TooManyPromotedPieces(p) =>
+                write!(f, "{p:?} has more promoted pieces than missing pawns!"),
+            SetupError::PawnOnBackRank => write!(f, "Pawns cannot stand on the first or last rank!"),
+            SetupError::OpponentInCheck =>
+                write!(f, "The side not to move is in check!"),
+            SetupError::InvalidCastlingRights =>
+                write!(f, "Castling rights require the king and rook on their starting squares!"),
+            SetupError::InvalidEnPassant =>
+                write!(f, "The en passant square does not follow a two square pawn move!"),
+        }
+    }
+}
+
 pub struct Board {
     pub turn: i32,
+    pub halfmove_clock: i32,
     pub state: game::GameState,
+    // each piece carries its own last move, which castling, en passant and FEN output read back
+    // empty squares are boxed zero-sized values, so cloning a board only allocates for the pieces on it
     pub squares: [Box<dyn pieces::Piece>; 64],
-    pub white_king: (i8, i8),
-    pub black_king: (i8, i8),
+    // positions of each side's pieces (and kings), kept in sync with the squares
+    white_pieces: Vec<(i8, i8)>,
+    black_pieces: Vec<(i8, i8)>,
+    white_king: Option<(i8, i8)>,
+    black_king: Option<(i8, i8)>,
 }
 
 impl Board {
     pub fn new() -> Board {
         Board {
             turn: 0,
+            halfmove_clock: 0,
             state: game::GameState::Playing(game::Player::White),
             squares: array_init::array_init(
                 |_| Box::new(pieces::empty::Empty {}) as Box<dyn pieces::Piece>
             ),
-            white_king: (0, 0),
-            black_king: (0, 0),
-        }
-    }
-
-    pub fn clone(&self) -> Board {
-        let mut clone = Board {
-            turn: self.turn,
-            state: self.state,
-            squares: array_init::array_init(
-                |_| Box::new(pieces::empty::Empty {}) as Box<dyn pieces::Piece>
-            ),
-            white_king: (self.white_king.0, self.white_king.1),
-            black_king: (self.black_king.0, self.black_king.1),
-        };
-        for f in 0..8 {
-            for r in 0..8 {
-                let piece = &self.squares[convert_position_1d(f, r)];
-                if let Some(p) = piece.get_player() {
-                    clone.squares[convert_position_1d(f, r)] = new_boxed_piece(
-                        p,
-                        piece.get_type(),
-                        f,
-                        r
-                    );
-                }
-            }
+            white_pieces: Vec::new(),
+            black_pieces: Vec::new(),
+            white_king: None,
+            black_king: None,
         }
-        return clone;
     }
 
     pub fn get_turn(&self) -> i32 {
@@ -67,9 +87,26 @@ impl Board {
         self.state = state;
     }
 
+    pub fn get_king(&self, player: game::Player) -> Option<(i8, i8)> {
+        return match player {
+            game::Player::White => self.white_king,
+            game::Player::Black => self.black_king,
+        };
+    }
+
+    // the squares of all of a player's pieces, including the king
+    pub fn get_pieces(&self, player: game::Player) -> &Vec<(i8, i8)> {
+        return match player {
+            game::Player::White => &self.white_pieces,
+            game::Player::Black => &self.black_pieces,
+        };
+    }
+
     pub fn reset_board(&mut self) {
-        for i in 0..64 {
-            self.squares[i] = Box::new(pieces::empty::Empty {}) as Box<dyn pieces::Piece>;
+        for f in 0..8 {
+            for r in 0..8 {
+                self.clear_square(f, r);
+            }
         }
 
         // white pieces
@@ -97,9 +134,6 @@ impl Board {
         for i in 0..8 {
             self.place_piece(game::Player::Black, pieces::PieceType::Pawn, i, 6);
         }
-
-        self.white_king = (4, 0);
-        self.black_king = (4, 7);
     }
 
     // place a piece regardless of move validity
@@ -112,16 +146,54 @@ impl Board {
         file: i8,
         rank: i8
     ) {
-        let index = convert_position_1d(file, rank);
-        self.squares[index] = new_boxed_piece(player, piece_type, file, rank);
+        self.set_square(file, rank, new_boxed_piece(player, piece_type, file, rank));
     }
 
     // clear a square
     pub fn clear_square(&mut self, file: i8, rank: i8) {
+        self.set_square(file, rank, Box::new(pieces::empty::Empty {}) as Box<dyn pieces::Piece>);
+    }
+
+    // every change to the squares goes through here so the piece lists and kings stay in sync
+    fn set_square(&mut self, file: i8, rank: i8, piece: Box<dyn pieces::Piece>) {
         let index = convert_position_1d(file, rank);
-        self.squares[index] = Box::new(pieces::empty::Empty {}) as Box<dyn pieces::Piece>;
+
+        // forget the piece being replaced
+        if let Some(p) = self.squares[index].get_player() {
+            let (list, king) = match p {
+                game::Player::White => (&mut self.white_pieces, &mut self.white_king),
+                game::Player::Black => (&mut self.black_pieces, &mut self.black_king),
+            };
+            list.retain(|square| *square != (file, rank));
+            if *king == Some((file, rank)) {
+                // fall back to any other king the player has on the board
+                *king = None;
+                for square in list.iter() {
+                    let piece = &self.squares[convert_position_1d(square.0, square.1)];
+                    if piece.get_type() == pieces::PieceType::King {
+                        *king = Some(*square);
+                        break;
+                    }
+                }
+            }
+        }
+
+        // remember the piece being placed
+        if let Some(p) = piece.get_player() {
+            let (list, king) = match p {
+                game::Player::White => (&mut self.white_pieces, &mut self.white_king),
+                game::Player::Black => (&mut self.black_pieces, &mut self.black_king),
+            };
+            list.push((file, rank));
+            if piece.get_type() == pieces::PieceType::King {
+                *king = Some((file, rank));
+            }
+        }
+
+        self.squares[index] = piece;
     }
 
+    #[allow(clippy::redundant_pattern_matching)]
     pub fn piece_can_move(&self, player: game::Player, mv: moves::PieceMove) -> bool {
         let piece = &self.squares[convert_position_1d(mv.src_file, mv.src_rank)];
         if piece.can_attack(self, mv.dst_file, mv.dst_rank) {
@@ -133,8 +205,95 @@ impl Board {
         return false;
     }
 
+    // is the move capturing an enemy piece (including en passant)?
+    pub fn is_capture(&self, mv: moves::PieceMove) -> bool {
+        let src = &self.squares[convert_position_1d(mv.src_file, mv.src_rank)];
+        let dst = &self.squares[convert_position_1d(mv.dst_file, mv.dst_rank)];
+        if let (Some(p), Some(q)) = (src.get_player(), dst.get_player()) {
+            return p != q;
+        }
+        return src.get_type() == pieces::PieceType::Pawn && mv.src_file != mv.dst_file;
+    }
+
+    // returns the squares of all of a player's pieces attacking a given square
+    pub fn get_attackers(&self, player: game::Player, file: i8, rank: i8) -> Vec<(i8, i8)> {
+        let mut attackers: Vec<(i8, i8)> = Vec::new();
+        for &(f, r) in self.get_pieces(player) {
+            if self.squares[convert_position_1d(f, r)].can_attack(self, file, rank) {
+                attackers.push((f, r));
+            }
+        }
+        return attackers;
+    }
+
+    // static exchange evaluation
+    // plays out every capture on the move's destination square, each side always recapturing
+    // with its least valuable attacker, and returns the material balance in centipawns for the
+    // moving player; either side may stop capturing once continuing would lose material
+    // attackers hidden behind sliders (x-rays) join in as the pieces in front are traded off
+    pub fn see(&self, mv: moves::PieceMove) -> i32 {
+        let player = match self.squares[convert_position_1d(mv.src_file, mv.src_rank)].get_player() {
+            Some(p) => p,
+            None => {
+                return 0;
+            }
+        };
+
+        let mut board = self.clone();
+        let mut gains: Vec<i32> = Vec::new();
+
+        // the first capture may be en passant or a promotion
+        let mut captured = board.squares[convert_position_1d(mv.dst_file, mv.dst_rank)].get_type();
+        if captured == pieces::PieceType::Empty && self.is_capture(mv) {
+            captured = pieces::PieceType::Pawn;
+            board.clear_square(mv.dst_file, mv.src_rank);
+        }
+        let moved = board.squares[convert_position_1d(mv.src_file, mv.src_rank)].get_type();
+        gains.push(captured.value() + mv.piece_type.value() - moved.value());
+        board.clear_square(mv.src_file, mv.src_rank);
+        board.place_piece(player, mv.piece_type, mv.dst_file, mv.dst_rank);
+
+        let mut occupant = mv.piece_type;
+        let mut side = other_player(player);
+        loop {
+            let attacker = board
+                .get_attackers(side, mv.dst_file, mv.dst_rank)
+                .into_iter()
+                .min_by_key(|a| board.squares[convert_position_1d(a.0, a.1)].get_type().value());
+            let (attacker_file, attacker_rank) = match attacker {
+                Some(a) => a,
+                None => {
+                    break;
+                }
+            };
+            let attacker_type =
+                board.squares[convert_position_1d(attacker_file, attacker_rank)].get_type();
+
+            // the king may only recapture if the square is no longer defended
+            if
+                attacker_type == pieces::PieceType::King &&
+                !board.get_attackers(other_player(side), mv.dst_file, mv.dst_rank).is_empty()
+            {
+                break;
+            }
+
+            gains.push(occupant.value() - gains[gains.len() - 1]);
+            board.clear_square(attacker_file, attacker_rank);
+            board.place_piece(side, attacker_type, mv.dst_file, mv.dst_rank);
+            occupant = attacker_type;
+            side = other_player(side);
+        }
+
+        // work backwards through the exchange: each side only continues if it pays off
+        for i in (1..gains.len()).rev() {
+            gains[i - 1] = -std::cmp::max(-gains[i - 1], gains[i]);
+        }
+        return gains[0];
+    }
+
     // clears a src square and places a piece at dst square
     // does not check if piece can MOVE there or not, just whether the resulting position is valid
+    #[allow(clippy::len_zero)]
     fn execute_move(
         &mut self,
         player: Option<game::Player>,
@@ -156,19 +315,9 @@ impl Board {
         let src_index = convert_position_1d(mv.src_file, mv.src_rank);
         let dst_index = convert_position_1d(mv.dst_file, mv.dst_rank);
 
-        let is_king_move = self.squares[src_index].get_type() == pieces::PieceType::King;
-        // update stored king positions if it is a king move
-        if is_king_move {
-            match player {
-                game::Player::White => {
-                    self.white_king = (mv.dst_file, mv.dst_rank);
-                }
-                game::Player::Black => {
-                    self.black_king = (mv.dst_file, mv.dst_rank);
-                }
-            }
-        }
-
+        // pawn moves and captures reset the fifty move rule counter
+        let resets_clock =
+            self.squares[src_index].get_type() == pieces::PieceType::Pawn || self.is_capture(mv);
         // check if it is en passant
         if
             self.squares[src_index].get_type() == pieces::PieceType::Pawn &&
@@ -205,23 +354,15 @@ impl Board {
         if enemy_checking_pieces.len() > 0 {
             // undo moves
             for removed_piece in removed_pieces {
-                self.squares[convert_position_1d(removed_piece.0, removed_piece.1)] =
-                    removed_piece.2;
-            }
-
-            if is_king_move {
-                match player {
-                    game::Player::White => {
-                        self.white_king = (mv.src_file, mv.src_rank);
-                    }
-                    game::Player::Black => {
-                        self.black_king = (mv.src_file, mv.src_rank);
-                    }
-                }
+                self.set_square(removed_piece.0, removed_piece.1, removed_piece.2);
             }
             return Err(moves::MoveError::MoveIntoCheck);
         }
 
+        self.squares[dst_index].set_last_move(self.turn, mv);
+        self.turn += 1;
+        self.halfmove_clock = if resets_clock { 0 } else { self.halfmove_clock + 1 };
+
         if ally_checking_pieces.len() > 0 {
             let mut can_stop_checkmate = true;
             for attacker in ally_checking_pieces {
@@ -243,9 +384,6 @@ impl Board {
             }
         }
 
-        self.squares[dst_index].set_last_move(self.turn, mv);
-        self.turn += 1;
-
         self.set_state(GameState::Playing(other_player(player)));
         return Ok(moves::MoveOutcome::Continue);
     }
@@ -282,56 +420,377 @@ impl Board {
             }
         });
 
-        match notation {
-            "O-O" => self.king_castle(player),
-            "O-O-O" => self.queen_castle(player),
+        let piece_move = notation::parse_notation(self, &player, notation);
+        match piece_move {
+            Ok(mv) => {
+                return self.apply_move(player, mv);
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // play a move given as squares rather than notation
+    // castling is given as the king moving two squares towards the rook
+    pub fn make_move(
+        &mut self,
+        player: Option<game::Player>,
+        mv: moves::PieceMove
+    ) -> Result<moves::MoveOutcome, moves::MoveError> {
+        // check if game still in playing state; extract current player
+        let player = player.unwrap_or(match self.state {
+            game::GameState::Playing(p) => p,
+            game::GameState::Draw => {
+                return Err(moves::MoveError::InvalidMove);
+            }
+            game::GameState::Won(_) => {
+                return Err(moves::MoveError::InvalidMove);
+            }
+        });
+
+        if
+            !(0..8).contains(&mv.src_file) ||
+            !(0..8).contains(&mv.src_rank) ||
+            !(0..8).contains(&mv.dst_file) ||
+            !(0..8).contains(&mv.dst_rank)
+        {
+            return Err(moves::MoveError::InvalidMove);
+        }
+        let piece = &self.squares[convert_position_1d(mv.src_file, mv.src_rank)];
+        if piece.get_player() != Some(player) {
+            return Err(moves::MoveError::InvalidMove);
+        }
+
+        if is_castle(piece.get_type(), mv) {
+            return self.apply_move(player, mv);
+        }
+        if !piece.can_move(self, mv.dst_file, mv.dst_rank) {
+            return Err(moves::MoveError::InvalidMove);
+        }
+
+        // pawns must promote on the last rank, and only pawns can change type
+        let last_rank = if player == game::Player::White { 7 } else { 0 };
+        if piece.get_type() == pieces::PieceType::Pawn && mv.dst_rank == last_rank {
+            if
+                mv.piece_type == pieces::PieceType::Pawn ||
+                mv.piece_type == pieces::PieceType::King ||
+                mv.piece_type == pieces::PieceType::Empty
+            {
+                return Err(moves::MoveError::InvalidPromotion);
+            }
+        } else if mv.piece_type != piece.get_type() {
+            return Err(moves::MoveError::InvalidPromotion);
+        }
+
+        return self.apply_move(player, mv);
+    }
+
+    // play an already validated move
+    // a player left without a legal move who isn't in check is stalemated
+    pub(crate) fn apply_move(
+        &mut self,
+        player: game::Player,
+        mv: moves::PieceMove
+    ) -> Result<moves::MoveOutcome, moves::MoveError> {
+        let piece_type = self.squares[convert_position_1d(mv.src_file, mv.src_rank)].get_type();
+        let outcome = if is_castle(piece_type, mv) || is_castle(mv.piece_type, mv) {
+            // a castle that can't be played is refused, never played as an ordinary king move
+            let castle_rank = match player {
+                game::Player::White => 0,
+                game::Player::Black => 7,
+            };
+            if piece_type != pieces::PieceType::King || mv.src_rank != castle_rank {
+                return Err(moves::MoveError::InvalidMove);
+            }
+            if mv.dst_file > mv.src_file {
+                self.king_castle(player)?
+            } else {
+                self.queen_castle(player)?
+            }
+        } else {
+            self.execute_move(Some(player), mv)?
+        };
+
+        if let game::GameState::Playing(p) = self.state {
+            if !self.has_legal_move(p) && !self.is_in_check(p) {
+                self.set_state(GameState::Draw);
+                return Ok(moves::MoveOutcome::Draw);
+            }
+        }
+        return Ok(outcome);
+    }
+
+    // all the moves the side to move can legally play
+    // promotions are listed once for each piece the pawn can become
+    pub fn legal_moves(&self) -> Vec<moves::PieceMove> {
+        let player = match self.state {
+            game::GameState::Playing(p) => p,
             _ => {
-                let piece_move = notation::parse_notation(self, &player, &notation);
-                match piece_move {
-                    Ok(mv) => {
-                        return self.execute_move(Some(player), mv);
+                return Vec::new();
+            }
+        };
+        let mut legal = Vec::new();
+        for mv in self.pseudo_legal_moves(player) {
+            if self.is_legal(player, mv) {
+                legal.push(mv);
+            }
+        }
+        return legal;
+    }
+
+    // can the player make any move at all? (stops at the first one found)
+    fn has_legal_move(&self, player: game::Player) -> bool {
+        return self
+            .pseudo_legal_moves(player)
+            .into_iter()
+            .any(|mv| self.is_legal(player, mv));
+    }
+
+    // moves that follow the pieces' movement rules, but may leave the king in check
+    fn pseudo_legal_moves(&self, player: game::Player) -> Vec<moves::PieceMove> {
+        let mut candidates: Vec<moves::PieceMove> = Vec::new();
+        let is_free = |f: i8, r: i8| {
+            (0..8).contains(&f) &&
+                (0..8).contains(&r) &&
+                self.squares[convert_position_1d(f, r)].get_player() != Some(player)
+        };
+
+        for &(f, r) in self.get_pieces(player) {
+            let piece_type = self.squares[convert_position_1d(f, r)].get_type();
+            let mut push = |df: i8, dr: i8, piece_type: pieces::PieceType| {
+                candidates.push(moves::PieceMove::new(piece_type, f, r, df, dr));
+            };
+            match piece_type {
+                pieces::PieceType::King | pieces::PieceType::Knight => {
+                    let steps: &[(i8, i8)] = if piece_type == pieces::PieceType::King {
+                        &[(1, 1), (1, 0), (0, 1), (-1, 0), (0, -1), (-1, -1), (1, -1), (-1, 1)]
+                    } else {
+                        &[(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)]
+                    };
+                    for (x, y) in steps {
+                        if is_free(f + x, r + y) {
+                            push(f + x, r + y, piece_type);
+                        }
+                    }
+                    if piece_type == pieces::PieceType::King {
+                        for dst_file in [6, 2] {
+                            let mv = moves::PieceMove::new(piece_type, f, r, dst_file, r);
+                            if is_castle(piece_type, mv) && self.can_castle(r, if dst_file == 6 { 7 } else { 0 }).is_ok() {
+                                push(dst_file, r, piece_type);
+                            }
+                        }
                     }
-                    Err(e) => Err(e),
                 }
+                pieces::PieceType::Queen | pieces::PieceType::Rook | pieces::PieceType::Bishop => {
+                    let directions: &[(i8, i8)] = match piece_type {
+                        pieces::PieceType::Rook => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+                        pieces::PieceType::Bishop => &[(1, 1), (1, -1), (-1, 1), (-1, -1)],
+                        _ => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)],
+                    };
+                    for (x, y) in directions {
+                        let (mut df, mut dr) = (f + x, r + y);
+                        while is_free(df, dr) {
+                            push(df, dr, piece_type);
+                            if self.squares[convert_position_1d(df, dr)].get_player().is_some() {
+                                break;
+                            }
+                            df += x;
+                            dr += y;
+                        }
+                    }
+                }
+                pieces::PieceType::Pawn => {
+                    let (direction, start_rank, last_rank) = match player {
+                        game::Player::White => (1, 1, 7),
+                        game::Player::Black => (-1, 6, 0),
+                    };
+                    let mut destinations: Vec<(i8, i8)> = Vec::new();
+                    let empty = |f: i8, r: i8| {
+                        (0..8).contains(&r) &&
+                            self.squares[convert_position_1d(f, r)].get_type() == pieces::PieceType::Empty
+                    };
+                    if empty(f, r + direction) {
+                        destinations.push((f, r + direction));
+                        if r == start_rank && empty(f, r + 2 * direction) {
+                            destinations.push((f, r + 2 * direction));
+                        }
+                    }
+                    for df in [f - 1, f + 1] {
+                        if !(0..8).contains(&df) || !(0..8).contains(&(r + direction)) {
+                            continue;
+                        }
+                        let target = &self.squares[convert_position_1d(df, r + direction)];
+                        let en_passant = match self.squares[convert_position_1d(df, r)].get_last_move() {
+                            Some((turn, mv)) =>
+                                target.get_type() == pieces::PieceType::Empty &&
+                                    self.squares[convert_position_1d(df, r)].get_type() == pieces::PieceType::Pawn &&
+                                    *turn == self.turn - 1 &&
+                                    (mv.src_rank - mv.dst_rank).abs() == 2,
+                            None => false,
+                        };
+                        if target.get_player() == Some(other_player(player)) || en_passant {
+                            destinations.push((df, r + direction));
+                        }
+                    }
+                    for (df, dr) in destinations {
+                        if dr == last_rank {
+                            for promotion in [
+                                pieces::PieceType::Queen,
+                                pieces::PieceType::Rook,
+                                pieces::PieceType::Bishop,
+                                pieces::PieceType::Knight,
+                            ] {
+                                push(df, dr, promotion);
+                            }
+                        } else {
+                            push(df, dr, piece_type);
+                        }
+                    }
+                }
+                pieces::PieceType::Empty => {}
             }
         }
+        return candidates;
+    }
+
+    // does a pseudo-legal move keep the player's king safe?
+    fn is_legal(&self, player: game::Player, mv: moves::PieceMove) -> bool {
+        let piece_type = self.squares[convert_position_1d(mv.src_file, mv.src_rank)].get_type();
+        // castling was fully checked when it was generated
+        if is_castle(piece_type, mv) {
+            return true;
+        }
+        let mut board = self.clone();
+        if self.is_capture(mv) && self.squares[convert_position_1d(mv.dst_file, mv.dst_rank)].get_player().is_none() {
+            board.clear_square(mv.dst_file, mv.src_rank);
+        }
+        board.clear_square(mv.src_file, mv.src_rank);
+        board.place_piece(player, mv.piece_type, mv.dst_file, mv.dst_rank);
+        return !board.is_in_check(player);
     }
 
     // returns a tuple (0, 1) where:
     // 0: a list of enemy pieces attacking friendly king
     // 1: a list of friendly pieces attacking enemy king
+    #[allow(clippy::type_complexity)]
     fn get_checking_pieces(&self, defender: game::Player) -> (Vec<(i8, i8)>, Vec<(i8, i8)>) {
         let mut enemies: Vec<(i8, i8)> = Vec::new();
         let mut allies: Vec<(i8, i8)> = Vec::new();
 
-        let (king, enemy_king) = match defender {
-            game::Player::White => (self.white_king, self.black_king),
-            game::Player::Black => (self.black_king, self.white_king),
+        // if enemy piece, check if can attack friendly king
+        if let Some(king) = self.get_king(defender) {
+            enemies = self.get_attackers(other_player(defender), king.0, king.1);
+        }
+        // if friendly piece, check if can attack enemy king
+        if let Some(enemy_king) = self.get_king(other_player(defender)) {
+            allies = self.get_attackers(defender, enemy_king.0, enemy_king.1);
+        }
+        return (enemies, allies);
+    }
+
+    // is the player's king currently attacked?
+    pub fn is_in_check(&self, player: game::Player) -> bool {
+        return match self.get_king(player) {
+            Some(king) => !self.get_attackers(other_player(player), king.0, king.1).is_empty(),
+            None => false,
+        };
+    }
+
+    // ensure the position could be reached in a legal game
+    // usage: checking boards set up by hand (place_piece) or loaded from FEN
+    pub fn validate(&self) -> Result<(), SetupError> {
+        for player in [game::Player::White, game::Player::Black] {
+            let mut kings: Vec<(i8, i8)> = Vec::new();
+            let mut counts = [0; 7];
+            for f in 0..8 {
+                for r in 0..8 {
+                    let piece = &self.squares[convert_position_1d(f, r)];
+                    if piece.get_player() != Some(player) {
+                        continue;
+                    }
+                    if piece.get_type() == pieces::PieceType::King {
+                        kings.push((f, r));
+                    }
+                    counts[piece.get_type() as usize] += 1;
+                }
+            }
+
+            if kings.is_empty() {
+                return Err(SetupError::MissingKing(player));
+            }
+            if kings.len() > 1 {
+                return Err(SetupError::ExtraKing(player));
+            }
+            if self.get_king(player) != Some(kings[0]) {
+                return Err(SetupError::KingPositionMismatch(player));
+            }
+
+            if counts.iter().sum::<i32>() > 16 {
+                return Err(SetupError::TooManyPieces(player));
+            }
+
+            // every piece beyond the starting set must have been a pawn once
+            let promoted =
+                (counts[pieces::PieceType::Queen as usize] - 1).max(0) +
+                (counts[pieces::PieceType::Rook as usize] - 2).max(0) +
+                (counts[pieces::PieceType::Bishop as usize] - 2).max(0) +
+                (counts[pieces::PieceType::Knight as usize] - 2).max(0);
+            if counts[pieces::PieceType::Pawn as usize] + promoted > 8 {
+                return Err(SetupError::TooManyPromotedPieces(player));
+            }
+        }
+
+        for f in 0..8 {
+            for r in [0, 7] {
+                if self.squares[convert_position_1d(f, r)].get_type() == pieces::PieceType::Pawn {
+                    return Err(SetupError::PawnOnBackRank);
+                }
+            }
+        }
+
+        let to_move = match self.state {
+            game::GameState::Playing(p) => p,
+            _ => {
+                return Ok(());
+            }
         };
 
+        // only the opponent's last move can be a two square pawn move available for en passant
         for f in 0..8 {
             for r in 0..8 {
-                let attacker = &self.squares[convert_position_1d(f, r)];
-                if let Some(p) = attacker.get_player() {
-                    // if enemy piece, check if can attack given square
-                    if p != defender {
-                        if attacker.can_attack(self, king.0, king.1) {
-                            enemies.push((f, r));
-                        }
-                    } else {
-                        // if friendly piece, check if can attack enemy king
-                        if attacker.can_attack(self, enemy_king.0, enemy_king.1) {
-                            allies.push((f, r));
-                        }
+                let piece = &self.squares[convert_position_1d(f, r)];
+                if piece.get_type() != pieces::PieceType::Pawn {
+                    continue;
+                }
+                if let Some((turn, mv)) = piece.get_last_move() {
+                    if *turn != self.turn - 1 || (mv.dst_rank - mv.src_rank).abs() != 2 {
+                        continue;
+                    }
+                    let passed_rank = (mv.src_rank + mv.dst_rank) / 2;
+                    if
+                        piece.get_player() == Some(to_move) ||
+                        mv.dst_file != f ||
+                        mv.dst_rank != r ||
+                        self.squares[convert_position_1d(f, passed_rank)].get_type() !=
+                            pieces::PieceType::Empty ||
+                        self.squares[convert_position_1d(f, mv.src_rank)].get_type() !=
+                            pieces::PieceType::Empty
+                    {
+                        return Err(SetupError::InvalidEnPassant);
                     }
                 }
             }
         }
-        return (enemies, allies);
+
+        if self.is_in_check(other_player(to_move)) {
+            return Err(SetupError::OpponentInCheck);
+        }
+        return Ok(());
     }
 
     // check if a player can castle (helper)
-    fn can_castle(&mut self, castle_rank: i8, rook_file: i8) -> Result<(), moves::MoveError> {
+    // the king may not castle out of, through or into check
+    #[allow(clippy::partialeq_to_none)]
+    fn can_castle(&self, castle_rank: i8, rook_file: i8) -> Result<(), moves::MoveError> {
         if castle_rank != 0 && castle_rank != 7 {
             return Err(moves::MoveError::InvalidMove);
         }
@@ -343,6 +802,8 @@ impl Board {
         if
             king.get_type() == pieces::PieceType::King &&
             rook.get_type() == pieces::PieceType::Rook &&
+            king.get_player() == Some(defender) &&
+            rook.get_player() == Some(defender) &&
             king.get_last_move() == None &&
             rook.get_last_move() == None
         {
@@ -357,22 +818,14 @@ impl Board {
                 }
             }
 
-            // ensure that king will not pass through check during castle
-            if rook_file == 0 {
-                return match self.is_path_under_attack(defender, 2, castle_rank, 4, castle_rank) {
-                    true => {
-                        return Err(moves::MoveError::MoveIntoCheck);
-                    }
-                    false => Ok(()),
-                };
-            } else {
-                return match self.is_path_under_attack(defender, 4, castle_rank, 6, castle_rank) {
-                    true => {
-                        return Err(moves::MoveError::MoveIntoCheck);
-                    }
-                    false => Ok(()),
-                };
+            // ensure that the king is not in check and will not pass through or land in check
+            let king_path = if rook_file == 0 { 2..=4 } else { 4..=6 };
+            for i in king_path {
+                if !self.get_attackers(other_player(defender), i, castle_rank).is_empty() {
+                    return Err(moves::MoveError::MoveIntoCheck);
+                }
             }
+            return Ok(());
         }
         return Err(moves::MoveError::InvalidMove);
     }
@@ -393,14 +846,7 @@ impl Board {
                 self.clear_square(4, castle_rank);
                 self.clear_square(7, castle_rank);
                 self.turn += 1;
-                match player {
-                    game::Player::White => {
-                        self.white_king = (6, 0);
-                    }
-                    game::Player::Black => {
-                        self.black_king = (6, 7);
-                    }
-                }
+                self.halfmove_clock += 1;
                 self.set_state(GameState::Playing(other_player(player)));
                 return Ok(moves::MoveOutcome::Continue);
             }
@@ -426,14 +872,7 @@ impl Board {
                 self.clear_square(4, castle_rank);
                 self.clear_square(0, castle_rank);
                 self.turn += 1;
-                match player {
-                    game::Player::White => {
-                        self.white_king = (2, 0);
-                    }
-                    game::Player::Black => {
-                        self.black_king = (2, 7);
-                    }
-                }
+                self.halfmove_clock += 1;
                 self.set_state(GameState::Playing(other_player(player)));
                 return Ok(moves::MoveOutcome::Continue);
             }
@@ -495,6 +934,7 @@ impl Board {
         return false;
     }
 
+    #[allow(clippy::manual_range_contains)]
     fn can_stop_check(
         &mut self,
         attacker: game::Player,
@@ -502,9 +942,11 @@ impl Board {
         attacker_file: i8,
         attacker_rank: i8
     ) -> bool {
-        let king = match attacker {
-            game::Player::White => self.black_king,
-            game::Player::Black => self.white_king,
+        let king = match self.get_king(other_player(attacker)) {
+            Some(k) => k,
+            None => {
+                return true;
+            }
         };
 
         // test if the king can move out of check
@@ -526,38 +968,64 @@ impl Board {
                 dst_file < 8 &&
                 dst_rank >= 0 &&
                 dst_rank < 8 &&
-                self.piece_can_move(
-                    other_player(attacker),
-                    PieceMove::new(pieces::PieceType::King, king.0, king.1, dst_file, dst_rank)
-                )
+                self.squares[convert_position_1d(king.0, king.1)].can_move(self, dst_file, dst_rank)
             {
                 return true;
             }
         }
 
-        // knight can't be blocked, don't check for it
-        if attacker_type == pieces::PieceType::Knight {
-            for f in 0..8 {
-                for r in 0..8 {
-                    let piece = &self.squares[convert_position_1d(f, r)];
-                    if let Some(p) = piece.get_player() {
-                        let tmp_mv = moves::PieceMove::new(
-                            self.squares[convert_position_1d(f, r)].get_type(),
-                            f,
-                            r,
-                            attacker_file,
-                            attacker_rank
-                        );
-                        if p != attacker && self.piece_can_move(p, tmp_mv) {
-                            return true;
-                        }
-                    }
+        // the check can also be stopped by capturing the checking piece or, unless it is a knight
+        // (which can't be blocked), by moving a piece in between it and the king
+        let mut stopping_squares: Vec<(i8, i8)> = vec![(attacker_file, attacker_rank)];
+        if attacker_type != pieces::PieceType::Knight {
+            let mut curr_file = attacker_file + (king.0 - attacker_file).signum();
+            let mut curr_rank = attacker_rank + (king.1 - attacker_rank).signum();
+            while (curr_file, curr_rank) != king {
+                stopping_squares.push((curr_file, curr_rank));
+                curr_file += (king.0 - curr_file).signum();
+                curr_rank += (king.1 - curr_rank).signum();
+            }
+        }
+
+        let defender = other_player(attacker);
+        for &(f, r) in self.get_pieces(defender) {
+            let piece = &self.squares[convert_position_1d(f, r)];
+            // king moves were tested above
+            if piece.get_type() == pieces::PieceType::King {
+                continue;
+            }
+            for &(dst_file, dst_rank) in &stopping_squares {
+                let mv = moves::PieceMove::new(piece.get_type(), f, r, dst_file, dst_rank);
+                if
+                    piece.can_move(self, dst_file, dst_rank) &&
+                    self.clone().execute_move(Some(defender), mv).is_ok()
+                {
+                    return true;
                 }
             }
-            return false;
         }
-        // the player inflicting check is "defending" the path between the checking piece and the enemy king
-        return self.is_path_under_attack(attacker, attacker_file, attacker_rank, king.0, king.1);
+        return false;
+    }
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        return Board::new();
+    }
+}
+
+impl Clone for Board {
+    fn clone(&self) -> Board {
+        return Board {
+            turn: self.turn,
+            halfmove_clock: self.halfmove_clock,
+            state: self.state,
+            squares: array_init::array_init(|i| dyn_clone::clone_box(&*self.squares[i])),
+            white_pieces: self.white_pieces.clone(),
+            black_pieces: self.black_pieces.clone(),
+            white_king: self.white_king,
+            black_king: self.black_king,
+        };
     }
 }
 
@@ -589,6 +1057,14 @@ impl std::fmt::Display for Board {
     }
 }
 
+// is this a king moving two squares along its starting rank?
+pub fn is_castle(piece_type: pieces::PieceType, mv: moves::PieceMove) -> bool {
+    return piece_type == pieces::PieceType::King &&
+        mv.src_file == 4 &&
+        mv.src_rank == mv.dst_rank &&
+        (mv.dst_file - mv.src_file).abs() == 2;
+}
+
 // convert a file and rank to a square index on a 1d board array
 pub fn convert_position_1d(file: i8, rank: i8) -> usize {
     return (8 * (7 - rank) + file) as usize;
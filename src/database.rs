@@ -0,0 +1,327 @@
+use crate::board;
+use crate::game;
+use crate::moves;
+use crate::notation;
+use crate::pgn;
+use crate::pieces;
+use crate::validate;
+use std::io::{ Read, Seek, SeekFrom, Write };
+
+// a compact binary game database
+//
+// file layout (all integers little endian):
+//   header   "CCDB" and a format version byte
+//   games    one record per game:
+//              tag count (u16), then for each tag: name length (u8), name, value length (u16), value
+//              result (u8: 0 = *, 1 = 1-0, 2 = 0-1, 3 = 1/2-1/2)
+//              move count (u16), then one u16 per move (see encode_move)
+//   index    the file offset of every game record (u64 each)
+//   footer   offset of the index (u64) and number of games (u64)
+//
+// only the main line is stored; comments and variations are dropped
+const MAGIC: &[u8; 4] = b"CCDB";
+const VERSION: u8 = 1;
+const HEADER_SIZE: u64 = 5;
+const FOOTER_SIZE: i64 = 16;
+
+// a game as stored in the database
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StoredGame {
+    pub tags: Vec<(String, String)>,
+    pub result: String,
+    pub moves: Vec<u16>,
+}
+
+impl StoredGame {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        return self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    // the position the game starts from
+    pub fn starting_board(&self) -> Result<board::Board, board::SetupError> {
+        return pgn::starting_board(self.get_tag("FEN"));
+    }
+}
+
+// pack a move into 16 bits: source square (bits 0-5), destination square (bits 6-11)
+// and promotion piece (bits 12-14: 0 none, 1 knight, 2 bishop, 3 rook, 4 queen)
+// squares are numbered a1 = 0, b1 = 1, ..., h8 = 63
+pub fn encode_move(board: &board::Board, mv: moves::PieceMove) -> u16 {
+    let src = (mv.src_rank as u16) * 8 + (mv.src_file as u16);
+    let dst = (mv.dst_rank as u16) * 8 + (mv.dst_file as u16);
+    let piece = &board.squares[board::convert_position_1d(mv.src_file, mv.src_rank)];
+    let promotion: u16 = if
+        piece.get_type() == pieces::PieceType::Pawn &&
+        mv.piece_type != pieces::PieceType::Pawn
+    {
+        match mv.piece_type {
+            pieces::PieceType::Knight => 1,
+            pieces::PieceType::Bishop => 2,
+            pieces::PieceType::Rook => 3,
+            _ => 4,
+        }
+    } else {
+        0
+    };
+    return src | (dst << 6) | (promotion << 12);
+}
+
+// unpack a move, taking the type of the moving piece from the board it is played on
+pub fn decode_move(board: &board::Board, code: u16) -> moves::PieceMove {
+    let (src, dst) = ((code & 63) as i8, ((code >> 6) & 63) as i8);
+    let piece_type = match (code >> 12) & 7 {
+        1 => pieces::PieceType::Knight,
+        2 => pieces::PieceType::Bishop,
+        3 => pieces::PieceType::Rook,
+        4 => pieces::PieceType::Queen,
+        _ => board.squares[board::convert_position_1d(src % 8, src / 8)].get_type(),
+    };
+    return moves::PieceMove::new(piece_type, src % 8, src / 8, dst % 8, dst / 8);
+}
+
+// convert the main line of a PGN game for storage
+pub fn encode_game(pgn_game: &pgn::PgnGame) -> Result<StoredGame, validate::ValidationIssue> {
+    let mut stored = StoredGame {
+        tags: pgn_game.tags.clone(),
+        result: pgn_game.result.clone(),
+        moves: Vec::new(),
+    };
+    let mut board = stored.starting_board().map_err(validate::ValidationIssue::InvalidSetup)?;
+    for pgn_move in &pgn_game.moves {
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => {
+                return Err(pgn::illegal_move(&board, &pgn_move.san, moves::MoveError::InvalidMove));
+            }
+        };
+        let mv = notation
+            ::parse_notation(&board, &player, &pgn_move.san)
+            .map_err(|e| pgn::illegal_move(&board, &pgn_move.san, e))?;
+        stored.moves.push(encode_move(&board, mv));
+        board.make_move(Some(player), mv).map_err(|e| pgn::illegal_move(&board, &pgn_move.san, e))?;
+    }
+    return Ok(stored);
+}
+
+// turn a stored game back into PGN, regenerating the SAN of each move
+pub fn decode_game(stored: &StoredGame) -> Result<pgn::PgnGame, validate::ValidationIssue> {
+    let mut board = stored.starting_board().map_err(validate::ValidationIssue::InvalidSetup)?;
+    let mut pgn_moves = Vec::new();
+    for &code in &stored.moves {
+        let mv = decode_move(&board, code);
+        let san = notation::to_san(&board, mv);
+        board.make_move(None, mv).map_err(|e| pgn::illegal_move(&board, &san, e))?;
+        pgn_moves.push(pgn::PgnMove {
+            san,
+            ..pgn::PgnMove::default()
+        });
+    }
+    return Ok(pgn::PgnGame {
+        tags: stored.tags.clone(),
+        comments: Vec::new(),
+        moves: pgn_moves,
+        result: stored.result.clone(),
+    });
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    return std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+}
+
+// writes games one after another, then the index once finished
+pub struct DatabaseWriter<W: Write> {
+    writer: W,
+    // offset at which the next game will be written
+    position: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: Write> DatabaseWriter<W> {
+    pub fn new(mut writer: W) -> std::io::Result<DatabaseWriter<W>> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        return Ok(DatabaseWriter {
+            writer,
+            position: HEADER_SIZE,
+            offsets: Vec::new(),
+        });
+    }
+
+    pub fn add_game(&mut self, stored: &StoredGame) -> std::io::Result<()> {
+        let mut record: Vec<u8> = Vec::new();
+        if stored.tags.len() > (u16::MAX as usize) || stored.moves.len() > (u16::MAX as usize) {
+            return Err(invalid_data("game has too many tags or moves"));
+        }
+        record.extend((stored.tags.len() as u16).to_le_bytes());
+        for (name, value) in &stored.tags {
+            if name.len() > (u8::MAX as usize) || value.len() > (u16::MAX as usize) {
+                return Err(invalid_data("tag is too long"));
+            }
+            record.push(name.len() as u8);
+            record.extend(name.as_bytes());
+            record.extend((value.len() as u16).to_le_bytes());
+            record.extend(value.as_bytes());
+        }
+        record.push(match stored.result.as_str() {
+            "1-0" => 1,
+            "0-1" => 2,
+            "1/2-1/2" => 3,
+            _ => 0,
+        });
+        record.extend((stored.moves.len() as u16).to_le_bytes());
+        for code in &stored.moves {
+            record.extend(code.to_le_bytes());
+        }
+
+        self.writer.write_all(&record)?;
+        self.offsets.push(self.position);
+        self.position += record.len() as u64;
+        return Ok(());
+    }
+
+    // write the index and footer, returning the underlying writer
+    pub fn finish(mut self) -> std::io::Result<W> {
+        for offset in &self.offsets {
+            self.writer.write_all(&offset.to_le_bytes())?;
+        }
+        self.writer.write_all(&self.position.to_le_bytes())?;
+        self.writer.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        self.writer.flush()?;
+        return Ok(self.writer);
+    }
+}
+
+// reads games by number using the index
+pub struct Database<R: Read + Seek> {
+    reader: R,
+    offsets: Vec<u64>,
+}
+
+impl<R: Read + Seek> Database<R> {
+    pub fn open(mut reader: R) -> std::io::Result<Database<R>> {
+        let mut header = [0u8; HEADER_SIZE as usize];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid_data("not a game database"));
+        }
+
+        reader.seek(SeekFrom::End(-FOOTER_SIZE))?;
+        let index_offset = read_u64(&mut reader)?;
+        let count = read_u64(&mut reader)?;
+        reader.seek(SeekFrom::Start(index_offset))?;
+        let mut offsets = Vec::new();
+        for _ in 0..count {
+            offsets.push(read_u64(&mut reader)?);
+        }
+        return Ok(Database {
+            reader,
+            offsets,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.offsets.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.offsets.is_empty();
+    }
+
+    // read a game by its (0-based) number
+    pub fn get(&mut self, game: usize) -> std::io::Result<StoredGame> {
+        let offset = *self.offsets.get(game).ok_or_else(|| invalid_data("no such game"))?;
+        let reader = &mut self.reader;
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut stored = StoredGame::default();
+        let tag_count = read_u16(reader)?;
+        for _ in 0..tag_count {
+            let mut length = [0u8; 1];
+            reader.read_exact(&mut length)?;
+            let name = read_string(reader, length[0] as usize)?;
+            let length = read_u16(reader)? as usize;
+            let value = read_string(reader, length)?;
+            stored.tags.push((name, value));
+        }
+        let mut result = [0u8; 1];
+        reader.read_exact(&mut result)?;
+        stored.result = (match result[0] {
+            1 => "1-0",
+            2 => "0-1",
+            3 => "1/2-1/2",
+            _ => "*",
+        }).to_string();
+        let move_count = read_u16(reader)?;
+        for _ in 0..move_count {
+            stored.moves.push(read_u16(reader)?);
+        }
+        return Ok(stored);
+    }
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> std::io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u16::from_le_bytes(bytes));
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u64::from_le_bytes(bytes));
+}
+
+fn read_string<R: Read>(reader: &mut R, length: usize) -> std::io::Result<String> {
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    return String::from_utf8(bytes).map_err(|_| invalid_data("tag is not valid UTF-8"));
+}
+
+// convert every game in a PGN source into a database
+// games that can't be read or replayed are left out and reported instead
+pub fn pgn_to_database<R: std::io::BufRead, W: Write>(
+    reader: R,
+    writer: W
+) -> std::io::Result<Vec<validate::ValidationReport>> {
+    let mut database = DatabaseWriter::new(writer)?;
+    let mut reports = Vec::new();
+    let mut games = pgn::PgnReader::new(reader);
+    while let Some(result) = games.next() {
+        let number = games.game_number();
+        let stored = match result {
+            Ok(pgn_game) => encode_game(&pgn_game),
+            Err(e) => Err(validate::ValidationIssue::Syntax(e)),
+        };
+        match stored {
+            Ok(stored) => database.add_game(&stored)?,
+            Err(issue) => reports.push(validate::ValidationReport { game: number, issue }),
+        }
+    }
+    database.finish()?;
+    return Ok(reports);
+}
+
+// write every game in a database as PGN, separated by blank lines
+pub fn database_to_pgn<R: Read + Seek, W: Write>(
+    database: &mut Database<R>,
+    writer: &mut W
+) -> std::io::Result<Vec<validate::ValidationReport>> {
+    let mut reports = Vec::new();
+    let mut first = true;
+    for i in 0..database.len() {
+        match decode_game(&database.get(i)?) {
+            Ok(pgn_game) => {
+                if !first {
+                    writeln!(writer)?;
+                }
+                write!(writer, "{}", pgn::write_pgn(&pgn_game))?;
+                first = false;
+            }
+            Err(issue) => reports.push(validate::ValidationReport { game: i + 1, issue }),
+        }
+    }
+    return Ok(reports);
+}
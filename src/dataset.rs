@@ -0,0 +1,168 @@
+use crate::fen;
+use crate::game;
+use crate::notation;
+use crate::pgn;
+use crate::validate;
+use regex::Regex;
+use std::io::{ BufRead, Write };
+use std::sync::OnceLock;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DatasetFormat {
+    Csv,
+    JsonLines,
+}
+
+// one ply of a game: the position before the move and the move that was played from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetRow {
+    // 1-based number of the game in the file
+    pub game: usize,
+    // 1-based number of the ply in the game
+    pub ply: usize,
+    pub fen: String,
+    pub san: String,
+    pub uci: String,
+    // w or b
+    pub side: char,
+    pub result: String,
+    // the [%clk] and [%eval] annotations of the move, as written
+    pub clock: Option<String>,
+    pub eval: Option<String>,
+}
+
+const CSV_HEADER: &str = "game,ply,fen,san,uci,side,result,clock,eval";
+
+// replay the main line of a game, describing every ply
+pub fn dataset_rows(
+    pgn_game: &pgn::PgnGame,
+    game_number: usize
+) -> Result<Vec<DatasetRow>, validate::ValidationIssue> {
+    let mut board = pgn::starting_board(pgn_game.get_tag("FEN")).map_err(validate::ValidationIssue::InvalidSetup)?;
+    static CLOCK_REGEX: OnceLock<Regex> = OnceLock::new();
+    static EVAL_REGEX: OnceLock<Regex> = OnceLock::new();
+    let clock_regex = CLOCK_REGEX.get_or_init(|| Regex::new(r"\[%clk\s+([^\]\s]+)\s*\]").unwrap());
+    let eval_regex = EVAL_REGEX.get_or_init(|| Regex::new(r"\[%eval\s+([^\]\s]+)\s*\]").unwrap());
+    let annotation = |regex: &Regex, comments: &[String]| {
+        comments
+            .iter()
+            .find_map(|comment| regex.captures(comment).map(|caps| caps[1].to_string()))
+    };
+
+    let mut rows = Vec::new();
+    for (i, pgn_move) in pgn_game.moves.iter().enumerate() {
+        let player = pgn::side_to_move(&board);
+        let illegal = |error| pgn::illegal_move(&board, &pgn_move.san, error);
+        let mv = notation::parse_notation(&board, &player, &pgn_move.san).map_err(illegal)?;
+
+        rows.push(DatasetRow {
+            game: game_number,
+            ply: i + 1,
+            fen: fen::to_fen(&board),
+            san: notation::to_san(&board, mv),
+            uci: notation::to_uci(&board, mv),
+            side: match player {
+                game::Player::White => 'w',
+                game::Player::Black => 'b',
+            },
+            result: pgn_game.result.clone(),
+            clock: annotation(clock_regex, &pgn_move.comments),
+            eval: annotation(eval_regex, &pgn_move.comments),
+        });
+        board.make_move(Some(player), mv).map_err(|error| pgn::illegal_move(&board, &pgn_move.san, error))?;
+    }
+    return Ok(rows);
+}
+
+// quote a CSV field if it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+    return field.to_string();
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    return json;
+}
+
+impl DatasetRow {
+    pub fn to_csv(&self) -> String {
+        return [
+            self.game.to_string(),
+            self.ply.to_string(),
+            csv_field(&self.fen),
+            csv_field(&self.san),
+            csv_field(&self.uci),
+            self.side.to_string(),
+            csv_field(&self.result),
+            csv_field(self.clock.as_deref().unwrap_or("")),
+            csv_field(self.eval.as_deref().unwrap_or("")),
+        ].join(",");
+    }
+
+    pub fn to_json(&self) -> String {
+        let optional = |value: &Option<String>| value.as_deref().map_or("null".to_string(), json_string);
+        return format!(
+            "{{\"game\":{},\"ply\":{},\"fen\":{},\"san\":{},\"uci\":{},\"side\":{},\"result\":{},\"clock\":{},\"eval\":{}}}",
+            self.game,
+            self.ply,
+            json_string(&self.fen),
+            json_string(&self.san),
+            json_string(&self.uci),
+            json_string(&self.side.to_string()),
+            json_string(&self.result),
+            optional(&self.clock),
+            optional(&self.eval)
+        );
+    }
+}
+
+// write one row per ply of every game in a PGN source
+// games that can't be read or replayed are left out and reported instead
+pub fn export_dataset<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    format: DatasetFormat
+) -> std::io::Result<Vec<validate::ValidationReport>> {
+    if format == DatasetFormat::Csv {
+        writeln!(writer, "{CSV_HEADER}")?;
+    }
+
+    let mut reports = Vec::new();
+    let mut games = pgn::PgnReader::new(reader);
+    while let Some(result) = games.next() {
+        let number = games.game_number();
+        let rows = match result {
+            Ok(pgn_game) => dataset_rows(&pgn_game, number),
+            Err(e) => Err(validate::ValidationIssue::Syntax(e)),
+        };
+        match rows {
+            Ok(rows) => {
+                for row in rows {
+                    match format {
+                        DatasetFormat::Csv => writeln!(writer, "{}", row.to_csv())?,
+                        DatasetFormat::JsonLines => writeln!(writer, "{}", row.to_json())?,
+                    }
+                }
+            }
+            Err(issue) => {
+                reports.push(validate::ValidationReport { game: number, issue });
+            }
+        }
+    }
+    return Ok(reports);
+}
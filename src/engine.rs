@@ -0,0 +1,707 @@
+use crate::board;
+use crate::evaluation;
+use crate::game;
+use crate::moves;
+use crate::pieces;
+use crate::transposition;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::sync::Arc;
+use std::time::{ Duration, Instant };
+
+// the score of delivering mate right away; mates further away score a little less
+pub const MATE: i32 = 100000;
+// any score beyond this is a forced mate
+pub(crate) const MATE_BOUND: i32 = MATE - 1000;
+const INFINITY: i32 = MATE + 1;
+// the transposition table size in megabytes unless configured otherwise
+pub const DEFAULT_HASH_SIZE: usize = 16;
+// how far a capture may seem to fall short of alpha before the quiescence search skips it
+const DELTA_MARGIN: i32 = 200;
+// move ordering scores for each class of move, far enough apart that the classes never mix
+const ORDER_STEP: i32 = 1_000_000;
+// history scores are halved before they could reach the killers
+const HISTORY_LIMIT: i32 = ORDER_STEP / 2;
+// the deepest a search with only a time limit goes
+pub const MAX_DEPTH: u32 = 64;
+// kept back from every move's time for the moves to be sent and played
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// assume the game lasts this many more moves when the time control doesn't say
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// how many nodes to search between looking at the clock and the abort signal
+const CHECK_INTERVAL: u64 = 256;
+
+// a search score from the point of view of the side to move
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Score {
+    Centipawns(i32),
+    // mate in the given number of moves; negative when the side to move is getting mated
+    Mate(i32),
+}
+
+impl Score {
+    pub fn from_value(value: i32) -> Score {
+        if value > MATE_BOUND {
+            return Score::Mate((MATE - value + 1) / 2);
+        }
+        if value < -MATE_BOUND {
+            return Score::Mate(-(MATE + value) / 2);
+        }
+        return Score::Centipawns(value);
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "{:+.2}", (*cp as f64) / 100.0),
+            Score::Mate(n) => write!(f, "#{n}"),
+        }
+    }
+}
+
+// the search stops at whichever limit it reaches first
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SearchLimits {
+    // the deepest iteration to search, in plies
+    pub depth: u32,
+    // a fixed amount of time for this move
+    pub move_time: Option<Duration>,
+    // the time left on the clock of the side to move, along with the time control
+    pub time_left: Option<Duration>,
+    pub increment: Duration,
+    // the moves left until the next time control, if the clock isn't for the rest of the game
+    pub moves_to_go: Option<u32>,
+    // stop after searching about this many nodes
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    // the time to aim for (soft limit), and the time the search must stop by (hard limit)
+    // None when the search isn't timed
+    // an iteration isn't started if it would likely end past the soft limit, while the hard limit
+    // aborts one in progress
+    pub fn time_budget(&self) -> Option<(Duration, Duration)> {
+        if let Some(move_time) = self.move_time {
+            let time = move_time.saturating_sub(MOVE_OVERHEAD).max(Duration::from_millis(1));
+            return Some((time, time));
+        }
+
+        let usable = self.time_left?.saturating_sub(MOVE_OVERHEAD).max(Duration::from_millis(1));
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, DEFAULT_MOVES_TO_GO);
+        let soft = usable / moves_to_go + self.increment * 3 / 4;
+        // never risk more than most of what is left on the clock
+        let hard = (soft * 3).min(usable * 3 / 4);
+        return Some((soft.min(hard), hard));
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> SearchLimits {
+        return SearchLimits {
+            depth: 4,
+            move_time: None,
+            time_left: None,
+            increment: Duration::ZERO,
+            moves_to_go: None,
+            nodes: None,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    // None when the side to move has no legal moves
+    pub best_move: Option<moves::PieceMove>,
+    pub score: Score,
+    // the line the search expects to be played, starting with the best move
+    pub pv: Vec<moves::PieceMove>,
+    // the depth of the last completed iteration
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    // per mille of the transposition table filled in by this search
+    pub hashfull: u32,
+}
+
+// a negamax alpha-beta search with iterative deepening
+// with several threads, helpers search the same position alongside the main thread and share what
+// they find through the transposition table (Lazy SMP); the main thread's result is the one returned
+pub struct Engine {
+    table: transposition::TranspositionTable,
+    // set from anywhere to stop the search
+    stop: Arc<AtomicBool>,
+    // one set of move ordering heuristics per thread
+    heuristics: Vec<Heuristics>,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        return Engine::with_hash_size(DEFAULT_HASH_SIZE);
+    }
+
+    // an engine whose transposition table uses about the given number of megabytes
+    pub fn with_hash_size(megabytes: usize) -> Engine {
+        return Engine {
+            table: transposition::TranspositionTable::new(megabytes),
+            stop: Arc::new(AtomicBool::new(false)),
+            heuristics: vec![Heuristics::new()],
+        };
+    }
+
+    // resizing throws away everything the table has learned
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.table = transposition::TranspositionTable::new(megabytes);
+    }
+
+    pub fn get_threads(&self) -> usize {
+        return self.heuristics.len();
+    }
+
+    // a single thread searches the same way every time, more threads don't
+    pub fn set_threads(&mut self, threads: usize) {
+        self.heuristics.resize_with(threads.max(1), Heuristics::new);
+    }
+
+    // forget earlier searches, e.g. when starting a new game
+    pub fn clear_hash(&mut self) {
+        self.table.clear();
+        self.heuristics.iter_mut().for_each(|h| *h = Heuristics::new());
+    }
+
+    // per mille of the transposition table used by the last search
+    pub fn hashfull(&self) -> u32 {
+        return self.table.hashfull();
+    }
+
+    // setting the signal makes a running search return the result of its last completed iteration
+    // the signal is cleared whenever a search starts
+    pub fn stop_signal(&self) -> Arc<AtomicBool> {
+        return Arc::clone(&self.stop);
+    }
+
+    pub fn search(&mut self, board: &board::Board, limits: &SearchLimits) -> SearchResult {
+        return self.search_with(board, limits, |_| {});
+    }
+
+    // search one ply deeper each iteration, reporting every completed iteration of the main thread
+    // each iteration finds the previous one's best moves in the transposition table and tries them first
+    // a timed search spends less of its time once the best move stops changing
+    pub fn search_with<F>(
+        &mut self,
+        board: &board::Board,
+        limits: &SearchLimits,
+        on_iteration: F
+    ) -> SearchResult
+        where F: FnMut(&SearchResult)
+    {
+        let start = Instant::now();
+        self.stop.store(false, Ordering::Relaxed);
+        self.table.new_search();
+        self.heuristics.iter_mut().for_each(|h| h.new_search());
+
+        // helpers stop as soon as the main thread is done
+        let finished = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        let shared = Shared {
+            table: &self.table,
+            stop: &self.stop,
+            finished: &finished,
+            nodes: &nodes,
+            start,
+            deadline: limits.time_budget().map(|(_, hard)| start + hard),
+            max_nodes: limits.nodes,
+        };
+        let (main, helpers) = self.heuristics.split_first_mut().unwrap();
+
+        let mut result = std::thread::scope(|scope| {
+            for (id, heuristics) in helpers.iter_mut().enumerate() {
+                let mut helper = Worker::new(&shared, heuristics);
+                scope.spawn(move || {
+                    helper.iterate(board, limits, id + 1, |_| {});
+                });
+            }
+            let result = Worker::new(&shared, main).iterate(board, limits, 0, on_iteration);
+            finished.store(true, Ordering::Relaxed);
+            return result;
+        });
+        // every thread has added its last few nodes by now
+        result.nodes = nodes.load(Ordering::Relaxed);
+        result.elapsed = start.elapsed();
+        result.hashfull = self.table.hashfull();
+        return result;
+    }
+
+    // how the main thread would order the moves, for the tests
+    #[cfg(test)]
+    pub(crate) fn order_moves(
+        &self,
+        board: &board::Board,
+        moves: &mut [moves::PieceMove],
+        hash_move: Option<moves::PieceMove>,
+        ply: i32
+    ) {
+        self.heuristics[0].order_moves(board, moves, hash_move, ply, None);
+    }
+}
+
+// what all the threads of one search share
+struct Shared<'a> {
+    table: &'a transposition::TranspositionTable,
+    stop: &'a AtomicBool,
+    finished: &'a AtomicBool,
+    // the nodes searched by every thread
+    nodes: &'a AtomicU64,
+    start: Instant,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+}
+
+// move ordering knowledge one thread gathers as it searches
+struct Heuristics {
+    // two quiet moves per ply that caused a cutoff, tried early in sibling positions
+    killers: Vec<[Option<moves::PieceMove>; 2]>,
+    // how often a quiet move from one square to another caused a cutoff, for each player
+    history: Vec<i32>,
+    // the quiet move that last refuted each move, indexed by that move's squares
+    countermoves: Vec<Option<moves::PieceMove>>,
+}
+
+impl Heuristics {
+    fn new() -> Heuristics {
+        return Heuristics {
+            killers: Vec::new(),
+            history: vec![0; 2 * 64 * 64],
+            countermoves: vec![None; 64 * 64],
+        };
+    }
+
+    // killers only apply to the positions of one search, while history fades more slowly
+    fn new_search(&mut self) {
+        self.killers.clear();
+        self.history.iter_mut().for_each(|h| *h /= 2);
+    }
+
+    // remember a quiet move that refuted the opponent's last move
+    fn record_cutoff(
+        &mut self,
+        player: game::Player,
+        mv: moves::PieceMove,
+        depth: u32,
+        ply: i32,
+        previous: Option<moves::PieceMove>
+    ) {
+        let ply = ply as usize;
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        if self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+
+        let index = history_index(player, mv);
+        self.history[index] += (depth * depth) as i32;
+        // keep history scores below the killers and countermoves
+        if self.history[index] > HISTORY_LIMIT {
+            self.history.iter_mut().for_each(|h| *h /= 2);
+        }
+
+        if let Some(previous) = previous {
+            self.countermoves[square_pair(previous)] = Some(mv);
+        }
+    }
+
+    // the hash move first, then captures and promotions by most valuable victim and least valuable
+    // attacker, then the killers, the countermove, and the rest of the quiet moves by history
+    fn order_moves(
+        &self,
+        board: &board::Board,
+        moves: &mut [moves::PieceMove],
+        hash_move: Option<moves::PieceMove>,
+        ply: i32,
+        previous: Option<moves::PieceMove>
+    ) {
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => {
+                return;
+            }
+        };
+        let killers = self.killers.get(ply as usize).copied().unwrap_or([None; 2]);
+        let countermove = previous.and_then(|previous| self.countermoves[square_pair(previous)]);
+
+        let score = |mv: &moves::PieceMove| -> i32 {
+            if Some(*mv) == hash_move {
+                return 4 * ORDER_STEP;
+            }
+            if board.is_capture(*mv) || is_promotion(board, *mv) {
+                let attacker = board.squares[board::convert_position_1d(mv.src_file, mv.src_rank)].get_type();
+                let victim = captured_value(board, *mv) + promotion_gain(board, *mv);
+                return 3 * ORDER_STEP + victim * 10 - attacker.value().min(pieces::PieceType::Queen.value()) / 10;
+            }
+            if Some(*mv) == killers[0] {
+                return 2 * ORDER_STEP + 2;
+            }
+            if Some(*mv) == killers[1] {
+                return 2 * ORDER_STEP + 1;
+            }
+            if Some(*mv) == countermove {
+                return 2 * ORDER_STEP;
+            }
+            return self.history[history_index(player, *mv)];
+        };
+        moves.sort_by_cached_key(|mv| -score(mv));
+    }
+}
+
+// one thread of a search
+struct Worker<'a> {
+    shared: &'a Shared<'a>,
+    heuristics: &'a mut Heuristics,
+    // the moves leading from the root to the position being searched
+    line: Vec<moves::PieceMove>,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl<'a> Worker<'a> {
+    fn new(shared: &'a Shared<'a>, heuristics: &'a mut Heuristics) -> Worker<'a> {
+        return Worker { shared, heuristics, line: Vec::new(), nodes: 0, aborted: false };
+    }
+
+    // helpers search every other depth one ply deeper, so that the threads don't all walk in step
+    // only the main thread (id 0) manages the time
+    fn iterate<F>(&mut self, board: &board::Board, limits: &SearchLimits, id: usize, mut on_iteration: F) -> SearchResult
+        where F: FnMut(&SearchResult)
+    {
+        let budget = limits.time_budget();
+        let mut result = SearchResult {
+            best_move: None,
+            score: Score::from_value(0),
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            hashfull: 0,
+        };
+
+        let mut stable_iterations = 0;
+        let mut depth = 1;
+        while depth <= limits.depth.max(1) {
+            let mut pv = Vec::new();
+            let value = self.negamax(board, depth, -INFINITY, INFINITY, 0, &mut pv);
+            if self.aborted {
+                // with no iteration completed, fall back on the best move found so far, or any move
+                if result.best_move.is_none() {
+                    result.best_move = pv.first().copied().or(board.legal_moves().first().copied());
+                    result.pv = result.best_move.into_iter().collect();
+                }
+                break;
+            }
+
+            if pv.first() == result.best_move.as_ref() {
+                stable_iterations += 1;
+            } else {
+                stable_iterations = 0;
+            }
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score: Score::from_value(value),
+                pv,
+                depth,
+                nodes: self.shared.nodes.load(Ordering::Relaxed) + self.nodes % CHECK_INTERVAL,
+                elapsed: self.shared.start.elapsed(),
+                hashfull: self.shared.table.hashfull(),
+            };
+            on_iteration(&result);
+
+            // a forced mate can't be improved on once the whole line fits in the search depth
+            // the quiescence search may spot it earlier, without the moves leading up to it
+            if result.best_move.is_none() || (value.abs() > MATE_BOUND && MATE - value.abs() <= depth as i32) {
+                break;
+            }
+
+            if let Some((soft, hard)) = budget.filter(|_| id == 0) {
+                let scale = match stable_iterations {
+                    0 if depth > 1 => 1.5,
+                    0..=2 => 1.0,
+                    _ => 0.5,
+                };
+                // the next iteration takes at least as long as all the ones before it
+                if self.shared.start.elapsed() * 2 >= soft.mul_f64(scale).min(hard) {
+                    break;
+                }
+            }
+            depth += if id % 2 == 1 && depth % 2 == 1 { 2 } else { 1 };
+        }
+        self.shared.nodes.fetch_add(self.nodes % CHECK_INTERVAL, Ordering::Relaxed);
+        return result;
+    }
+
+    // has the hard time limit passed, the node limit been reached, the stop signal been set or the
+    // main thread finished? only looked at every so often, since reading the clock isn't free
+    fn should_stop(&mut self) -> bool {
+        if !self.aborted && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let nodes = self.shared.nodes.fetch_add(CHECK_INTERVAL, Ordering::Relaxed) + CHECK_INTERVAL;
+            let out_of_time = self.shared.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            let out_of_nodes = self.shared.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes);
+            self.aborted = out_of_time ||
+                out_of_nodes ||
+                self.shared.stop.load(Ordering::Relaxed) ||
+                self.shared.finished.load(Ordering::Relaxed);
+        }
+        return self.aborted;
+    }
+
+    fn negamax(
+        &mut self,
+        board: &board::Board,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        pv: &mut Vec<moves::PieceMove>
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            // the previous move mated or stalemated
+            game::GameState::Won(_) => {
+                return -(MATE - ply);
+            }
+            game::GameState::Draw => {
+                return 0;
+            }
+        };
+        if board.halfmove_clock >= 100 {
+            return 0;
+        }
+        // settle the captures in progress before trusting the evaluation
+        if depth == 0 {
+            return self.quiescence(board, alpha, beta, ply, 0);
+        }
+
+        // a deep enough result for this position may settle it without searching
+        // the root always searches so that it has a move to return
+        let key = transposition::hash_position(board);
+        let entry = self.shared.table.probe(key, ply);
+        let hash_move = entry.and_then(|e| e.best_move);
+        if let Some(entry) = entry.filter(|e| e.depth >= depth && ply > 0) {
+            let cutoff = match entry.bound {
+                transposition::Bound::Exact => true,
+                transposition::Bound::Lower => entry.score >= beta,
+                transposition::Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                if entry.bound == transposition::Bound::Exact {
+                    *pv = self.table_line(board, depth);
+                }
+                return entry.score;
+            }
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.is_in_check(player) { -(MATE - ply) } else { 0 };
+        }
+        self.heuristics.order_moves(board, &mut moves, hash_move, ply, self.line.last().copied());
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            let mut child = board.clone();
+            if child.apply_move(player, mv).is_err() {
+                continue;
+            }
+            let mut child_pv = Vec::new();
+            self.line.push(mv);
+            let score = -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1, &mut child_pv);
+            self.line.pop();
+            // an unfinished search mustn't leave anything behind
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend(child_pv);
+                }
+            }
+            if alpha >= beta {
+                if !board.is_capture(mv) && !is_promotion(board, mv) {
+                    self.heuristics.record_cutoff(player, mv, depth, ply, self.line.last().copied());
+                }
+                break;
+            }
+        }
+        // make sure the root always has a move, even if every move loses
+        if pv.is_empty() && ply == 0 {
+            pv.extend(best_move);
+        }
+
+        let bound = if best <= original_alpha {
+            transposition::Bound::Upper
+        } else if best >= beta {
+            transposition::Bound::Lower
+        } else {
+            transposition::Bound::Exact
+        };
+        // a move that failed low isn't known to be best, so keep whatever move the table had
+        let stored_move = if bound == transposition::Bound::Upper { None } else { best_move };
+        self.shared.table.store(key, depth, bound, best, stored_move, ply);
+        return best;
+    }
+
+    // search captures and promotions until the position is quiet, plus checks on the first ply
+    // the side to move may always stand pat with the static evaluation unless it is in check
+    fn quiescence(&mut self, board: &board::Board, mut alpha: i32, beta: i32, ply: i32, qply: u32) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            game::GameState::Won(_) => {
+                return -(MATE - ply);
+            }
+            game::GameState::Draw => {
+                return 0;
+            }
+        };
+        if board.halfmove_clock >= 100 {
+            return 0;
+        }
+
+        // every way out of check has to be searched
+        let in_check = board.is_in_check(player);
+        let stand_pat = if in_check { -INFINITY } else { evaluation::evaluate(board, player) };
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -(MATE - ply) } else { 0 };
+        }
+        if !in_check {
+            moves.retain(|&mv| {
+                let promotion = is_promotion(board, mv);
+                if !board.is_capture(mv) && !promotion {
+                    return qply == 0 && gives_check(board, player, mv);
+                }
+                // even winning the piece for free can't bring the score back up to alpha
+                if stand_pat + captured_value(board, mv) + promotion_gain(board, mv) + DELTA_MARGIN < alpha {
+                    return false;
+                }
+                // captures that lose material once the exchange is played out
+                return !board.is_capture(mv) || board.see(mv) >= 0;
+            });
+        }
+        self.heuristics.order_moves(board, &mut moves, None, ply, self.line.last().copied());
+
+        let mut best = stand_pat;
+        for mv in moves {
+            let mut child = board.clone();
+            if child.apply_move(player, mv).is_err() {
+                continue;
+            }
+            let score = -self.quiescence(&child, -beta, -alpha, ply + 1, qply + 1);
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        return best;
+    }
+
+    // follow the best moves stored in the table, for a principal variation cut short by a table hit
+    fn table_line(&self, board: &board::Board, depth: u32) -> Vec<moves::PieceMove> {
+        let mut line = Vec::new();
+        let mut board = board.clone();
+        while (line.len() as u32) < depth {
+            let player = match board.get_state() {
+                game::GameState::Playing(p) => *p,
+                _ => {
+                    break;
+                }
+            };
+            let mv = match self.shared.table.probe(transposition::hash_position(&board), 0).and_then(|e| e.best_move) {
+                Some(mv) if board.legal_moves().contains(&mv) => mv,
+                _ => {
+                    break;
+                }
+            };
+            if board.apply_move(player, mv).is_err() {
+                break;
+            }
+            line.push(mv);
+        }
+        return line;
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        return Engine::new();
+    }
+}
+
+fn is_promotion(board: &board::Board, mv: moves::PieceMove) -> bool {
+    let moved = board.squares[board::convert_position_1d(mv.src_file, mv.src_rank)].get_type();
+    return moved == pieces::PieceType::Pawn && mv.piece_type != pieces::PieceType::Pawn;
+}
+
+fn promotion_gain(board: &board::Board, mv: moves::PieceMove) -> i32 {
+    if !is_promotion(board, mv) {
+        return 0;
+    }
+    return mv.piece_type.value() - pieces::PieceType::Pawn.value();
+}
+
+// the value of the piece a move captures, counting en passant as a pawn
+fn captured_value(board: &board::Board, mv: moves::PieceMove) -> i32 {
+    if !board.is_capture(mv) {
+        return 0;
+    }
+    let captured = board.squares[board::convert_position_1d(mv.dst_file, mv.dst_rank)].get_type();
+    return if captured == pieces::PieceType::Empty {
+        pieces::PieceType::Pawn.value()
+    } else {
+        captured.value()
+    };
+}
+
+// does the moved piece attack the enemy king from its new square?
+// discovered checks are missed, which only costs the quiescence search a few tactics
+fn gives_check(board: &board::Board, player: game::Player, mv: moves::PieceMove) -> bool {
+    return match board.get_king(game::other_player(player)) {
+        Some((file, rank)) => {
+            board::new_boxed_piece(player, mv.piece_type, mv.dst_file, mv.dst_rank).can_attack(board, file, rank)
+        }
+        None => false,
+    };
+}
+
+fn square_pair(mv: moves::PieceMove) -> usize {
+    return board::convert_position_1d(mv.src_file, mv.src_rank) * 64 + board::convert_position_1d(mv.dst_file, mv.dst_rank);
+}
+
+fn history_index(player: game::Player, mv: moves::PieceMove) -> usize {
+    return (player as usize) * 64 * 64 + square_pair(mv);
+}
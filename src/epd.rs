@@ -0,0 +1,168 @@
+use crate::board;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::notation;
+
+// a position in Extended Position Description, along with its operations
+// operations are kept in the order they were written, e.g. ("bm", ["Qg6"]) or ("id", ["WAC.001"])
+pub struct Epd {
+    pub board: board::Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl Epd {
+    pub fn new(board: board::Board) -> Epd {
+        return Epd {
+            board,
+            operations: Vec::new(),
+        };
+    }
+
+    pub fn get_operation(&self, opcode: &str) -> Option<&Vec<String>> {
+        return self.operations
+            .iter()
+            .find(|(code, _)| code == opcode)
+            .map(|(_, operands)| operands);
+    }
+
+    // add an operation, replacing any existing one with the same opcode
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(code, _)| code == opcode) {
+            Some(operation) => {
+                operation.1 = operands;
+            }
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+
+    // the moves of an operation like bm or am, each played from the position itself
+    pub fn get_moves(&self, opcode: &str) -> Result<Vec<moves::PieceMove>, moves::MoveError> {
+        let player = match self.board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => {
+                return Err(moves::MoveError::InvalidMove);
+            }
+        };
+        return self
+            .get_operation(opcode)
+            .map_or(&[] as &[String], |operands| operands.as_slice())
+            .iter()
+            .map(|san| notation::parse_notation(&self.board, &player, san))
+            .collect();
+    }
+}
+
+// read an EPD line: the first four FEN fields followed by operations, each ending with ';'
+// the hmvc and fmvn operations set the move counters
+pub fn parse_epd(epd: &str) -> Result<Epd, board::SetupError> {
+    let epd = epd.trim();
+    let mut fields: Vec<&str> = Vec::new();
+    let mut rest = epd;
+    for _ in 0..4 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(board::SetupError::InvalidFen);
+        }
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    let operations = parse_operations(rest)?;
+    let counter = |opcode: &str, default: &str| {
+        match operations.iter().find(|(code, _)| code == opcode) {
+            Some((_, operands)) if operands.len() == 1 => Ok(operands[0].clone()),
+            Some(_) => Err(board::SetupError::InvalidEpd),
+            None => Ok(default.to_string()),
+        }
+    };
+    let position = format!("{} {} {}", fields.join(" "), counter("hmvc", "0")?, counter("fmvn", "1")?);
+    let board = fen::parse_fen(&position)?;
+
+    return Ok(Epd {
+        board,
+        operations,
+    });
+}
+
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, board::SetupError> {
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_string = false;
+
+    // the final operation's ';' is often left out
+    let text = text.trim_end();
+    let terminated = if text.is_empty() || text.ends_with(';') {
+        text.to_string()
+    } else {
+        format!("{text};")
+    };
+    for c in terminated.chars() {
+        if in_string {
+            if c == '"' {
+                in_string = false;
+            } else {
+                word.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                quoted = true;
+            }
+            ';' | ' ' | '\t' => {
+                if !word.is_empty() || quoted {
+                    words.push(std::mem::take(&mut word));
+                    quoted = false;
+                }
+                if c == ';' {
+                    // an operation is an opcode followed by its operands
+                    if words.is_empty() {
+                        return Err(board::SetupError::InvalidEpd);
+                    }
+                    let opcode = words.remove(0);
+                    let valid_opcode =
+                        opcode.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) &&
+                        opcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if !valid_opcode {
+                        return Err(board::SetupError::InvalidEpd);
+                    }
+                    operations.push((opcode, std::mem::take(&mut words)));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+
+    if in_string {
+        return Err(board::SetupError::InvalidEpd);
+    }
+    return Ok(operations);
+}
+
+// write a position and its operations as an EPD line
+// operands with spaces or semicolons, and the id and comment operands, are quoted
+pub fn to_epd(epd: &Epd) -> String {
+    let position = fen::to_fen(&epd.board);
+    let mut line = position.split(' ').take(4).collect::<Vec<&str>>().join(" ");
+    for (opcode, operands) in &epd.operations {
+        line.push(' ');
+        line += opcode;
+        let always_quote = opcode == "id" ||
+            (opcode.len() == 2 && opcode.starts_with('c') && opcode.ends_with(|c: char| c.is_ascii_digit()));
+        for operand in operands {
+            line.push(' ');
+            if always_quote || operand.is_empty() || operand.contains([' ', '\t', ';']) {
+                line += &format!("\"{operand}\"");
+            } else {
+                line += operand;
+            }
+        }
+        line.push(';');
+    }
+    return line;
+}
@@ -0,0 +1,411 @@
+use crate::board;
+use crate::game;
+use crate::pieces;
+
+// the phase of the game goes from MIDDLEGAME with all the pieces on the board down to 0 with none
+const MIDDLEGAME: i32 = 24;
+
+// piece-square tables from White's point of view, laid out like a diagram with rank 8 first
+// Black looks up the square mirrored across the middle of the board
+const PAWN_TABLE: [[i32; 64]; 2] = [
+    [
+         0,  0,  0,  0,  0,  0,  0,  0,
+        50, 50, 50, 50, 50, 50, 50, 50,
+        10, 10, 20, 30, 30, 20, 10, 10,
+         5,  5, 10, 25, 25, 10,  5,  5,
+         0,  0,  0, 20, 20,  0,  0,  0,
+         5, -5,-10,  0,  0,-10, -5,  5,
+         5, 10, 10,-20,-20, 10, 10,  5,
+         0,  0,  0,  0,  0,  0,  0,  0,
+    ],
+    [
+         0,  0,  0,  0,  0,  0,  0,  0,
+        80, 80, 80, 80, 80, 80, 80, 80,
+        50, 50, 50, 50, 50, 50, 50, 50,
+        30, 30, 30, 30, 30, 30, 30, 30,
+        20, 20, 20, 20, 20, 20, 20, 20,
+        10, 10, 10, 10, 10, 10, 10, 10,
+        10, 10, 10, 10, 10, 10, 10, 10,
+         0,  0,  0,  0,  0,  0,  0,  0,
+    ],
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+const ROOK_TABLE: [i32; 64] = [
+      0,  0,  0,  0,  0,  0,  0,  0,
+      5, 10, 10, 10, 10, 10, 10,  5,
+     -5,  0,  0,  0,  0,  0,  0, -5,
+     -5,  0,  0,  0,  0,  0,  0, -5,
+     -5,  0,  0,  0,  0,  0,  0, -5,
+     -5,  0,  0,  0,  0,  0,  0, -5,
+     -5,  0,  0,  0,  0,  0,  0, -5,
+      0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+// the king hides behind its pawns in the middlegame and heads for the centre in the endgame
+const KING_TABLE: [[i32; 64]; 2] = [
+    [
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -20,-30,-30,-40,-40,-30,-30,-20,
+        -10,-20,-20,-20,-20,-20,-20,-10,
+         20, 20,  0,  0,  0,  0, 20, 20,
+         20, 30, 10,  0,  0, 10, 30, 20,
+    ],
+    [
+        -50,-40,-30,-20,-20,-30,-40,-50,
+        -30,-20,-10,  0,  0,-10,-20,-30,
+        -30,-10, 20, 30, 30, 20,-10,-30,
+        -30,-10, 30, 40, 40, 30,-10,-30,
+        -30,-10, 30, 40, 40, 30,-10,-30,
+        -30,-10, 20, 30, 30, 20,-10,-30,
+        -30,-30,  0,  0,  0,  0,-30,-30,
+        -50,-30,-30,-30,-30,-30,-30,-50,
+    ],
+];
+
+// middlegame and endgame bonus for each square a piece can move to
+const MOBILITY: [(i32, i32); 4] = [(4, 4), (5, 5), (2, 4), (1, 2)];
+// bonus for a passed pawn on each rank, counted from the player's own side
+const PASSED_PAWN: [(i32, i32); 8] = [(0, 0), (5, 10), (10, 15), (15, 25), (25, 45), (40, 75), (60, 120), (0, 0)];
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-10, -15);
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+// king safety only matters while there are pieces around to attack the king
+const PAWN_SHIELD: [i32; 2] = [10, 5];
+const KING_ZONE_ATTACK: i32 = -8;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const LINES: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+// one part of the evaluation, in centipawns for each player, already scaled by the game phase
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Term {
+    pub white: i32,
+    pub black: i32,
+}
+
+impl Term {
+    // positive when the term favours White
+    pub fn total(&self) -> i32 {
+        return self.white - self.black;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Evaluation {
+    pub material: Term,
+    pub piece_squares: Term,
+    pub mobility: Term,
+    pub king_safety: Term,
+    pub pawn_structure: Term,
+    pub bishop_pair: Term,
+    // from 24 with all the pieces on the board down to 0 in a pawn endgame
+    pub phase: i32,
+}
+
+impl Evaluation {
+    pub fn terms(&self) -> [(&'static str, Term); 6] {
+        return [
+            ("Material", self.material),
+            ("Piece-square tables", self.piece_squares),
+            ("Mobility", self.mobility),
+            ("King safety", self.king_safety),
+            ("Pawn structure", self.pawn_structure),
+            ("Bishop pair", self.bishop_pair),
+        ];
+    }
+
+    // positive when the position favours White
+    pub fn total(&self) -> i32 {
+        return self
+            .terms()
+            .iter()
+            .map(|(_, term)| term.total())
+            .sum();
+    }
+
+    // from the point of view of the given player
+    pub fn score(&self, player: game::Player) -> i32 {
+        return match player {
+            game::Player::White => self.total(),
+            game::Player::Black => -self.total(),
+        };
+    }
+}
+
+// shows each term in pawns, like the table of a classical engine's eval command
+impl std::fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let pawns = |cp: i32| -> f64 {
+            return (cp as f64) / 100.0;
+        };
+        writeln!(f, "{:<20} {:>7} {:>7} {:>7}", "Term", "White", "Black", "Total")?;
+        for (name, term) in self.terms() {
+            writeln!(
+                f,
+                "{:<20} {:>7.2} {:>7.2} {:>+7.2}",
+                name,
+                pawns(term.white),
+                pawns(term.black),
+                pawns(term.total())
+            )?;
+        }
+        writeln!(f, "{:<20} {:>23}", "Phase", format!("{}/{MIDDLEGAME}", self.phase))?;
+        write!(f, "{:<20} {:>+23.2}", "Evaluation (White)", pawns(self.total()))
+    }
+}
+
+// static evaluation in centipawns, from the point of view of the given player
+pub fn evaluate(board: &board::Board, player: game::Player) -> i32 {
+    return evaluate_position(board).score(player);
+}
+
+// score every term for both players
+pub fn evaluate_position(board: &board::Board) -> Evaluation {
+    let phase = game_phase(board);
+    let taper = |(middlegame, endgame): (i32, i32)| -> i32 {
+        return (middlegame * phase + endgame * (MIDDLEGAME - phase)) / MIDDLEGAME;
+    };
+
+    let mut evaluation = Evaluation { phase, ..Evaluation::default() };
+    for player in [game::Player::White, game::Player::Black] {
+        let mut material = 0;
+        let mut piece_squares = (0, 0);
+        let mut mobility = (0, 0);
+        let mut bishops = 0;
+        for &(file, rank) in board.get_pieces(player) {
+            let piece_type = board.squares[board::convert_position_1d(file, rank)].get_type();
+            if piece_type != pieces::PieceType::King {
+                material += piece_type.value();
+            }
+
+            let (middlegame, endgame) = piece_square(player, piece_type, file, rank);
+            piece_squares.0 += middlegame;
+            piece_squares.1 += endgame;
+
+            let weight = match piece_type {
+                pieces::PieceType::Knight => MOBILITY[0],
+                pieces::PieceType::Bishop => MOBILITY[1],
+                pieces::PieceType::Rook => MOBILITY[2],
+                pieces::PieceType::Queen => MOBILITY[3],
+                _ => (0, 0),
+            };
+            let squares = count_mobility(board, player, piece_type, file, rank);
+            mobility.0 += weight.0 * squares;
+            mobility.1 += weight.1 * squares;
+
+            if piece_type == pieces::PieceType::Bishop {
+                bishops += 1;
+            }
+        }
+
+        let terms = [
+            material,
+            taper(piece_squares),
+            taper(mobility),
+            king_safety(board, player) * phase / MIDDLEGAME,
+            taper(pawn_structure(board, player)),
+            if bishops >= 2 { taper(BISHOP_PAIR) } else { 0 },
+        ];
+        let fields = [
+            &mut evaluation.material,
+            &mut evaluation.piece_squares,
+            &mut evaluation.mobility,
+            &mut evaluation.king_safety,
+            &mut evaluation.pawn_structure,
+            &mut evaluation.bishop_pair,
+        ];
+        for (term, value) in fields.into_iter().zip(terms) {
+            match player {
+                game::Player::White => {
+                    term.white = value;
+                }
+                game::Player::Black => {
+                    term.black = value;
+                }
+            }
+        }
+    }
+    return evaluation;
+}
+
+// count the pieces left, weighting the heavier ones more
+fn game_phase(board: &board::Board) -> i32 {
+    let mut phase = 0;
+    for player in [game::Player::White, game::Player::Black] {
+        for &(file, rank) in board.get_pieces(player) {
+            phase += match board.squares[board::convert_position_1d(file, rank)].get_type() {
+                pieces::PieceType::Knight | pieces::PieceType::Bishop => 1,
+                pieces::PieceType::Rook => 2,
+                pieces::PieceType::Queen => 4,
+                _ => 0,
+            };
+        }
+    }
+    // extra promoted pieces don't make it more of a middlegame than the starting position
+    return phase.min(MIDDLEGAME);
+}
+
+fn piece_square(player: game::Player, piece_type: pieces::PieceType, file: i8, rank: i8) -> (i32, i32) {
+    let rank = if player == game::Player::White { rank } else { 7 - rank };
+    let square = board::convert_position_1d(file, rank);
+    return match piece_type {
+        pieces::PieceType::Pawn => (PAWN_TABLE[0][square], PAWN_TABLE[1][square]),
+        pieces::PieceType::Knight => (KNIGHT_TABLE[square], KNIGHT_TABLE[square]),
+        pieces::PieceType::Bishop => (BISHOP_TABLE[square], BISHOP_TABLE[square]),
+        pieces::PieceType::Rook => (ROOK_TABLE[square], ROOK_TABLE[square]),
+        pieces::PieceType::Queen => (QUEEN_TABLE[square], QUEEN_TABLE[square]),
+        pieces::PieceType::King => (KING_TABLE[0][square], KING_TABLE[1][square]),
+        pieces::PieceType::Empty => (0, 0),
+    };
+}
+
+// the squares a knight or slider could move to, ignoring pins
+fn count_mobility(board: &board::Board, player: game::Player, piece_type: pieces::PieceType, file: i8, rank: i8) -> i32 {
+    let (directions, slides): (&[(i8, i8)], bool) = match piece_type {
+        pieces::PieceType::Knight => (&KNIGHT_OFFSETS, false),
+        pieces::PieceType::Bishop => (&DIAGONALS, true),
+        pieces::PieceType::Rook => (&LINES, true),
+        pieces::PieceType::Queen => {
+            return count_mobility(board, player, pieces::PieceType::Bishop, file, rank) +
+                count_mobility(board, player, pieces::PieceType::Rook, file, rank);
+        }
+        _ => {
+            return 0;
+        }
+    };
+
+    let mut count = 0;
+    for &(df, dr) in directions {
+        let (mut f, mut r) = (file + df, rank + dr);
+        while (0..8).contains(&f) && (0..8).contains(&r) {
+            match board.squares[board::convert_position_1d(f, r)].get_player() {
+                Some(p) => {
+                    if p != player {
+                        count += 1;
+                    }
+                    break;
+                }
+                None => {
+                    count += 1;
+                }
+            }
+            if !slides {
+                break;
+            }
+            f += df;
+            r += dr;
+        }
+    }
+    return count;
+}
+
+// pawns in front of the king, less the squares around it the opponent attacks
+// scored for the middlegame only
+fn king_safety(board: &board::Board, player: game::Player) -> i32 {
+    let (king_file, king_rank) = match board.get_king(player) {
+        Some(king) => king,
+        None => {
+            return 0;
+        }
+    };
+    let forward = if player == game::Player::White { 1 } else { -1 };
+    let opponent = game::other_player(player);
+
+    let mut score = 0;
+    for f in king_file - 1..=king_file + 1 {
+        for (distance, bonus) in PAWN_SHIELD.iter().enumerate() {
+            let r = king_rank + forward * (distance as i8 + 1);
+            if !(0..8).contains(&f) || !(0..8).contains(&r) {
+                continue;
+            }
+            let piece = &board.squares[board::convert_position_1d(f, r)];
+            if piece.get_player() == Some(player) && piece.get_type() == pieces::PieceType::Pawn {
+                score += bonus;
+                break;
+            }
+        }
+    }
+    for f in king_file - 1..=king_file + 1 {
+        for r in king_rank - 1..=king_rank + 1 {
+            let inside = (0..8).contains(&f) && (0..8).contains(&r) && (f, r) != (king_file, king_rank);
+            if inside && !board.get_attackers(opponent, f, r).is_empty() {
+                score += KING_ZONE_ATTACK;
+            }
+        }
+    }
+    return score;
+}
+
+// doubled and isolated pawns are weak, passed pawns grow stronger as they advance
+fn pawn_structure(board: &board::Board, player: game::Player) -> (i32, i32) {
+    let pawns = |p: game::Player| -> Vec<(i8, i8)> {
+        return board
+            .get_pieces(p)
+            .iter()
+            .copied()
+            .filter(|&(f, r)| board.squares[board::convert_position_1d(f, r)].get_type() == pieces::PieceType::Pawn)
+            .collect();
+    };
+    let own = pawns(player);
+    let enemy = pawns(game::other_player(player));
+    let ahead = |rank: i8, other: i8| -> bool {
+        return if player == game::Player::White { other > rank } else { other < rank };
+    };
+
+    let mut score = (0, 0);
+    let mut add = |(middlegame, endgame): (i32, i32)| {
+        score.0 += middlegame;
+        score.1 += endgame;
+    };
+    for &(file, rank) in &own {
+        // only the pawns behind the first one on a file count as doubled
+        if own.iter().any(|&(f, r)| f == file && ahead(rank, r)) {
+            add(DOUBLED_PAWN);
+        }
+        if !own.iter().any(|&(f, _)| (f - file).abs() == 1) {
+            add(ISOLATED_PAWN);
+        }
+        if !enemy.iter().any(|&(f, r)| (f - file).abs() <= 1 && ahead(rank, r)) {
+            let relative_rank = if player == game::Player::White { rank } else { 7 - rank };
+            add(PASSED_PAWN[relative_rank as usize]);
+        }
+    }
+    return score;
+}
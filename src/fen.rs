@@ -0,0 +1,304 @@
+use crate::board;
+use crate::game;
+use crate::pieces;
+use crate::moves;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// build a board from Forsyth-Edwards Notation
+// the halfmove clock and fullmove number are optional (as in EPD)
+// the resulting position is validated before it is returned
+pub fn parse_fen(fen: &str) -> Result<board::Board, board::SetupError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() < 4 || fields.len() > 6 {
+        return Err(board::SetupError::InvalidFen);
+    }
+
+    let mut board = board::Board::new();
+
+    // piece placement, from rank 8 down to rank 1
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    if ranks.len() != 8 {
+        return Err(board::SetupError::InvalidFen);
+    }
+    for (i, rank_string) in ranks.iter().enumerate() {
+        let rank = 7 - (i as i8);
+        let mut file: i8 = 0;
+        for c in rank_string.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                if skip == 0 || skip > 8 {
+                    return Err(board::SetupError::InvalidFen);
+                }
+                file += skip as i8;
+            } else {
+                if !"KQRBNP".contains(c.to_ascii_uppercase()) || file > 7 {
+                    return Err(board::SetupError::InvalidFen);
+                }
+                let player = if c.is_ascii_uppercase() {
+                    game::Player::White
+                } else {
+                    game::Player::Black
+                };
+                let piece_type = pieces::PieceType::from_char(c.to_ascii_uppercase()).unwrap();
+                board.place_piece(player, piece_type, file, rank);
+                file += 1;
+            }
+        }
+        if file != 8 {
+            return Err(board::SetupError::InvalidFen);
+        }
+    }
+
+    // side to move
+    let player = match fields[1] {
+        "w" => game::Player::White,
+        "b" => game::Player::Black,
+        _ => {
+            return Err(board::SetupError::InvalidFen);
+        }
+    };
+    board.set_state(game::GameState::Playing(player));
+
+    // move counters
+    board.halfmove_clock = match fields.get(4) {
+        Some(s) => s.parse::<i32>().map_err(|_| board::SetupError::InvalidFen)?,
+        None => 0,
+    };
+    let fullmove = match fields.get(5) {
+        Some(s) => s.parse::<i32>().map_err(|_| board::SetupError::InvalidFen)?,
+        None => 1,
+    };
+    if board.halfmove_clock < 0 || fullmove < 1 {
+        return Err(board::SetupError::InvalidFen);
+    }
+    // both counters go up with every move, so keep them to half their range to leave room for the rest of the game
+    board.turn = (fullmove - 1)
+        .checked_mul(2)
+        .and_then(|turn| turn.checked_add(if player == game::Player::Black { 1 } else { 0 }))
+        .filter(|turn| *turn <= i32::MAX / 2)
+        .ok_or(board::SetupError::InvalidFen)?;
+    if board.halfmove_clock > i32::MAX / 2 {
+        return Err(board::SetupError::InvalidFen);
+    }
+
+    parse_castling_rights(&mut board, fields[2])?;
+    parse_en_passant(&mut board, player, fields[3])?;
+
+    board.validate()?;
+    return Ok(board);
+}
+
+// castling rights are not stored on the board; a king or rook that has never moved can castle
+// so rooks whose right has been lost are given a placeholder move
+fn parse_castling_rights(board: &mut board::Board, rights: &str) -> Result<(), board::SetupError> {
+    if rights != "-" && (rights.is_empty() || !rights.chars().all(|c| "KQkq".contains(c))) {
+        return Err(board::SetupError::InvalidFen);
+    }
+
+    for (player, rank) in [
+        (game::Player::White, 0),
+        (game::Player::Black, 7),
+    ] {
+        for (rook_file, right) in [
+            (7, 'K'),
+            (0, 'Q'),
+        ] {
+            let right = match player {
+                game::Player::White => right,
+                game::Player::Black => right.to_ascii_lowercase(),
+            };
+            let king = &board.squares[board::convert_position_1d(4, rank)];
+            let rook = &board.squares[board::convert_position_1d(rook_file, rank)];
+            let in_place =
+                king.get_type() == pieces::PieceType::King &&
+                king.get_player() == Some(player) &&
+                rook.get_type() == pieces::PieceType::Rook &&
+                rook.get_player() == Some(player);
+
+            if rights.contains(right) {
+                if !in_place {
+                    return Err(board::SetupError::InvalidCastlingRights);
+                }
+            } else if rook.get_type() == pieces::PieceType::Rook {
+                let mv = moves::PieceMove::new(
+                    pieces::PieceType::Rook,
+                    rook_file,
+                    rank,
+                    rook_file,
+                    rank
+                );
+                board.squares[board::convert_position_1d(rook_file, rank)].set_last_move(-1, mv);
+            }
+        }
+    }
+    return Ok(());
+}
+
+// en passant is derived from the last move of the capturable pawn, so recreate its double step
+fn parse_en_passant(
+    board: &mut board::Board,
+    player: game::Player,
+    square: &str
+) -> Result<(), board::SetupError> {
+    if square == "-" {
+        return Ok(());
+    }
+    let (file, rank) = parse_square(square).ok_or(board::SetupError::InvalidFen)?;
+
+    // the pawn that just moved belongs to the opponent and passed over the en passant square
+    let (expected_rank, direction) = match player {
+        game::Player::White => (5, -1),
+        game::Player::Black => (2, 1),
+    };
+    if rank != expected_rank {
+        return Err(board::SetupError::InvalidEnPassant);
+    }
+    let pawn_rank = rank + direction;
+    let pawn = &board.squares[board::convert_position_1d(file, pawn_rank)];
+    if
+        pawn.get_type() != pieces::PieceType::Pawn ||
+        pawn.get_player() != Some(game::other_player(player))
+    {
+        return Err(board::SetupError::InvalidEnPassant);
+    }
+
+    let mv = moves::PieceMove::new(pieces::PieceType::Pawn, file, rank - direction, file, pawn_rank);
+    let turn = board.turn - 1;
+    board.squares[board::convert_position_1d(file, pawn_rank)].set_last_move(turn, mv);
+    return Ok(());
+}
+
+// write a board as Forsyth-Edwards Notation
+pub fn to_fen(board: &board::Board) -> String {
+    let mut fen = String::new();
+
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for file in 0..8 {
+            let piece = &board.squares[board::convert_position_1d(file, rank)];
+            match piece.get_player() {
+                Some(p) => {
+                    if empty > 0 {
+                        fen += &empty.to_string();
+                        empty = 0;
+                    }
+                    let c = piece.get_type().to_char();
+                    fen.push(match p {
+                        game::Player::White => c,
+                        game::Player::Black => c.to_ascii_lowercase(),
+                    });
+                }
+                None => {
+                    empty += 1;
+                }
+            }
+        }
+        if empty > 0 {
+            fen += &empty.to_string();
+        }
+        if rank > 0 {
+            fen.push('/');
+        }
+    }
+
+    let player = side_to_move(board);
+    fen += match player {
+        game::Player::White => " w ",
+        game::Player::Black => " b ",
+    };
+
+    let mut rights = String::new();
+    for (p, rank) in [
+        (game::Player::White, 0),
+        (game::Player::Black, 7),
+    ] {
+        for (rook_file, right) in [
+            (7, 'K'),
+            (0, 'Q'),
+        ] {
+            if can_castle(board, p, rank, rook_file) {
+                rights.push(match p {
+                    game::Player::White => right,
+                    game::Player::Black => right.to_ascii_lowercase(),
+                });
+            }
+        }
+    }
+    fen += if rights.is_empty() { "-" } else { &rights };
+
+    fen += " ";
+    fen += &en_passant_target(board, player).map_or("-".to_string(), |(f, r)| square_name(f, r));
+
+    fen += &format!(" {} {}", board.halfmove_clock, board.turn / 2 + 1);
+    return fen;
+}
+
+// the player to move, even once the game is over
+fn side_to_move(board: &board::Board) -> game::Player {
+    match board.get_state() {
+        game::GameState::Playing(p) => *p,
+        _ => {
+            if board.get_turn() % 2 == 0 { game::Player::White } else { game::Player::Black }
+        }
+    }
+}
+
+// do the king and rook still have their castling right?
+pub(crate) fn can_castle(board: &board::Board, player: game::Player, rank: i8, rook_file: i8) -> bool {
+    let king = &board.squares[board::convert_position_1d(4, rank)];
+    let rook = &board.squares[board::convert_position_1d(rook_file, rank)];
+    return king.get_type() == pieces::PieceType::King &&
+        king.get_player() == Some(player) &&
+        king.get_last_move().is_none() &&
+        rook.get_type() == pieces::PieceType::Rook &&
+        rook.get_player() == Some(player) &&
+        rook.get_last_move().is_none();
+}
+
+// the square behind a pawn that just moved two squares, if an enemy pawn is beside it
+pub(crate) fn en_passant_target(board: &board::Board, player: game::Player) -> Option<(i8, i8)> {
+    let pawn_rank = match player {
+        game::Player::White => 4,
+        game::Player::Black => 3,
+    };
+    for file in 0..8 {
+        let pawn = &board.squares[board::convert_position_1d(file, pawn_rank)];
+        if pawn.get_type() != pieces::PieceType::Pawn || pawn.get_player() == Some(player) {
+            continue;
+        }
+        if let Some((turn, mv)) = pawn.get_last_move() {
+            if *turn != board.get_turn() - 1 || (mv.src_rank - mv.dst_rank).abs() != 2 {
+                continue;
+            }
+            for capturer_file in [file - 1, file + 1] {
+                if !(0..8).contains(&capturer_file) {
+                    continue;
+                }
+                let capturer = &board.squares[board::convert_position_1d(capturer_file, pawn_rank)];
+                if
+                    capturer.get_type() == pieces::PieceType::Pawn &&
+                    capturer.get_player() == Some(player)
+                {
+                    return Some((file, (mv.src_rank + mv.dst_rank) / 2));
+                }
+            }
+        }
+    }
+    return None;
+}
+
+// convert a square name (e.g. e4) to a file and rank
+pub fn parse_square(square: &str) -> Option<(i8, i8)> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+    if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    return Some((((file as u8) - b'a') as i8, ((rank as u8) - b'1') as i8));
+}
+
+// convert a file and rank to a square name (e.g. e4)
+pub fn square_name(file: i8, rank: i8) -> String {
+    return format!("{}{}", (b'a' + (file as u8)) as char, rank + 1);
+}
@@ -1,5 +1,13 @@
 use std::io;
+use std::time::Duration;
 use crate::board;
+use crate::engine;
+use crate::evaluation;
+use crate::fen;
+use crate::moves;
+use crate::notation;
+use crate::pgn;
+use crate::uci;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Player {
@@ -21,43 +29,641 @@ pub fn other_player(p: Player) -> Player {
     }
 }
 
-pub fn game_loop() {
-    let mut board = board::Board::new();
-    board.reset_board();
-    println!("{board}");
+// why a game is over
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Termination {
+    Unterminated,
+    Checkmate,
+    Stalemate,
+    Resignation,
+    DrawAgreement,
+    TimeForfeit,
+    Abandoned,
+}
+
+// a move as it was played
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMove {
+    pub san: String,
+    pub mv: moves::PieceMove,
+    // time left on the mover's clock after the move, if known
+    pub clock: Option<Duration>,
+}
+
+// a position in the game tree, along with the move that led to it and its annotations
+#[derive(Clone)]
+pub struct GameNode {
+    // None for the starting position
+    pub game_move: Option<GameMove>,
+    // numeric annotation glyphs for the move
+    pub nags: Vec<u8>,
+    // comments written before the move (only at the start of a variation)
+    pub starting_comments: Vec<String>,
+    // comments written after the move
+    pub comments: Vec<String>,
+    parent: Option<usize>,
+    // the first child continues the main line, the others are variations
+    children: Vec<usize>,
+    board: board::Board,
+}
+
+impl GameNode {
+    fn new(game_move: Option<GameMove>, parent: Option<usize>, board: board::Board) -> GameNode {
+        return GameNode {
+            game_move,
+            nags: Vec::new(),
+            starting_comments: Vec::new(),
+            comments: Vec::new(),
+            parent,
+            children: Vec::new(),
+            board,
+        };
+    }
+
+    pub fn get_parent(&self) -> Option<usize> {
+        return self.parent;
+    }
+
+    pub fn get_children(&self) -> &Vec<usize> {
+        return &self.children;
+    }
+
+    pub fn get_board(&self) -> &board::Board {
+        return &self.board;
+    }
+}
+
+// a tree of moves together with everything needed to describe the game
+// nodes are kept in a list and refer to each other by index; the starting position is node 0
+pub struct Game {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub termination: Termination,
+    nodes: Vec<GameNode>,
+    // the node being looked at; moves are played from here
+    current: usize,
+}
+
+impl Game {
+    pub fn new() -> Game {
+        let mut board = board::Board::new();
+        board.reset_board();
+        return Game::from_board(board);
+    }
+
+    // start a game from any position
+    pub fn from_board(board: board::Board) -> Game {
+        return Game {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            termination: Termination::Unterminated,
+            nodes: vec![GameNode::new(None, None, board)],
+            current: 0,
+        };
+    }
+
+    // the board at the current node
+    pub fn get_board(&self) -> &board::Board {
+        return &self.nodes[self.current].board;
+    }
+
+    pub fn get_node(&self, node: usize) -> &GameNode {
+        return &self.nodes[node];
+    }
+
+    pub fn get_current(&self) -> usize {
+        return self.current;
+    }
+
+    // the nodes from the starting position to the current one
+    fn get_path(&self) -> Vec<usize> {
+        let mut path = vec![self.current];
+        while let Some(parent) = self.nodes[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        return path;
+    }
+
+    // the nodes of the main line, starting with the starting position
+    fn get_main_path(&self) -> Vec<usize> {
+        let mut path = vec![0];
+        while let Some(&child) = self.nodes[*path.last().unwrap()].children.first() {
+            path.push(child);
+        }
+        return path;
+    }
+
+    // the moves leading to the current node
+    pub fn get_moves(&self) -> Vec<&GameMove> {
+        return self
+            .get_path()
+            .iter()
+            .filter_map(|&node| self.nodes[node].game_move.as_ref())
+            .collect();
+    }
+
+    // the moves of the main line, however far along the current node is
+    pub fn get_main_line(&self) -> Vec<&GameMove> {
+        return self
+            .get_main_path()
+            .iter()
+            .filter_map(|&node| self.nodes[node].game_move.as_ref())
+            .collect();
+    }
+
+    // FEN of every position leading to the current node, starting with the initial one
+    pub fn get_positions(&self) -> Vec<String> {
+        return self
+            .get_path()
+            .iter()
+            .map(|&node| fen::to_fen(&self.nodes[node].board))
+            .collect();
+    }
+
+    // the state at the end of the main line
+    pub fn get_result(&self) -> &GameState {
+        return self.nodes[*self.get_main_path().last().unwrap()].board.get_state();
+    }
+
+    // play a move (or result) given in algebraic notation
+    pub fn play_notation(&mut self, notation: &str) -> Result<moves::MoveOutcome, moves::MoveError> {
+        let player = match self.get_board().get_state() {
+            GameState::Playing(p) => *p,
+            _ => {
+                return Err(moves::MoveError::InvalidMove);
+            }
+        };
+        match notation {
+            "1-0" => {
+                self.resign(Player::Black);
+                return Ok(moves::MoveOutcome::Win);
+            }
+            "0-1" => {
+                self.resign(Player::White);
+                return Ok(moves::MoveOutcome::Win);
+            }
+            "1/2-1/2" => {
+                self.agree_draw();
+                return Ok(moves::MoveOutcome::Draw);
+            }
+            _ => {}
+        }
+
+        let mv = notation::parse_notation(self.get_board(), &player, notation)?;
+        return self.play_move(mv);
+    }
+
+    // play a move from the current node
+    // a move that is already in the tree is followed, otherwise it starts a new variation
+    pub fn play_move(&mut self, mv: moves::PieceMove) -> Result<moves::MoveOutcome, moves::MoveError> {
+        let player = match self.get_board().get_state() {
+            GameState::Playing(p) => *p,
+            _ => {
+                return Err(moves::MoveError::InvalidMove);
+            }
+        };
+
+        let existing = self.nodes[self.current].children
+            .iter()
+            .find(|&&child| self.nodes[child].game_move.as_ref().map(|m| m.mv) == Some(mv));
+        if let Some(&child) = existing {
+            self.current = child;
+            return Ok(match self.get_board().get_state() {
+                GameState::Won(_) => moves::MoveOutcome::Win,
+                GameState::Draw => moves::MoveOutcome::Draw,
+                GameState::Playing(_) => moves::MoveOutcome::Continue,
+            });
+        }
+
+        let mut board = self.get_board().clone();
+        let san = notation::to_san(&board, mv);
+        let outcome = board.make_move(Some(player), mv)?;
+        let game_move = GameMove {
+            san,
+            mv,
+            clock: None,
+        };
+        let node = self.nodes.len();
+        self.nodes.push(GameNode::new(Some(game_move), Some(self.current), board));
+        self.nodes[self.current].children.push(node);
+        self.current = node;
+
+        // a game only ends on its main line; a variation ending is just analysis
+        if self.is_main_line() {
+            match self.get_board().get_state() {
+                GameState::Won(_) => {
+                    self.termination = Termination::Checkmate;
+                }
+                GameState::Draw => {
+                    self.termination = Termination::Stalemate;
+                }
+                GameState::Playing(_) => {}
+            }
+        }
+        return Ok(outcome);
+    }
+
+    // jump to any node in the tree
+    pub fn go_to(&mut self, node: usize) {
+        if node < self.nodes.len() {
+            self.current = node;
+        }
+    }
+
+    // take back the last move
+    pub fn back(&mut self) -> bool {
+        match self.nodes[self.current].parent {
+            Some(parent) => {
+                self.current = parent;
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    // follow the main continuation from the current node
+    pub fn forward(&mut self) -> bool {
+        match self.nodes[self.current].children.first() {
+            Some(&child) => {
+                self.current = child;
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    // play the first move of a variation from the current node (1 is the first alternative)
+    pub fn enter_variation(&mut self, variation: usize) -> bool {
+        match self.nodes[self.current].children.get(variation) {
+            Some(&child) if variation > 0 => {
+                self.current = child;
+                return true;
+            }
+            _ => {
+                return false;
+            }
+        }
+    }
+
+    // the first node of the variation the current node is in, if it isn't on the main line
+    fn variation_start(&self) -> Option<usize> {
+        let mut node = self.current;
+        while let Some(parent) = self.nodes[node].parent {
+            if self.nodes[parent].children[0] != node {
+                return Some(node);
+            }
+            node = parent;
+        }
+        return None;
+    }
+
+    // is the current node on the main line?
+    pub fn is_main_line(&self) -> bool {
+        return self.variation_start().is_none();
+    }
+
+    // go back to where the current variation branched off
+    pub fn exit_variation(&mut self) -> bool {
+        match self.variation_start() {
+            Some(start) => {
+                self.current = self.nodes[start].parent.unwrap();
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    // make the current variation the main continuation where it branches off
+    pub fn promote_variation(&mut self) -> bool {
+        match self.variation_start() {
+            Some(start) => {
+                let parent = self.nodes[start].parent.unwrap();
+                let children = &mut self.nodes[parent].children;
+                children.retain(|&child| child != start);
+                children.insert(0, start);
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    // remove the current move and everything after it, going back to the previous position
+    pub fn delete_branch(&mut self) -> bool {
+        match self.nodes[self.current].parent {
+            Some(parent) => {
+                let node = self.current;
+                self.nodes[parent].children.retain(|&child| child != node);
+                self.current = parent;
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    // annotate the current node
+    pub fn add_comment(&mut self, comment: &str) {
+        self.nodes[self.current].comments.push(comment.to_string());
+    }
+
+    pub fn add_starting_comment(&mut self, comment: &str) {
+        self.nodes[self.current].starting_comments.push(comment.to_string());
+    }
+
+    pub fn add_nag(&mut self, nag: u8) {
+        self.nodes[self.current].nags.push(nag);
+    }
+
+    // record the time left on the clock of the player who made the current move
+    pub fn set_clock(&mut self, clock: Duration) {
+        if let Some(game_move) = self.nodes[self.current].game_move.as_mut() {
+            game_move.clock = Some(clock);
+        }
+    }
+
+    pub fn resign(&mut self, player: Player) {
+        let node = self.current;
+        self.nodes[node].board.set_state(GameState::Won(other_player(player)));
+        self.termination = Termination::Resignation;
+    }
+
+    pub fn agree_draw(&mut self) {
+        let node = self.current;
+        self.nodes[node].board.set_state(GameState::Draw);
+        self.termination = Termination::DrawAgreement;
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        return Game::new();
+    }
+}
+
+// today's date in PGN format (YYYY.MM.DD)
+fn today() -> String {
+    let seconds = std::time::SystemTime
+        ::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    // convert days since 1970-01-01 to a civil date
+    let days = seconds / 86400 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{year:04}.{month:02}.{day:02}");
+}
+
+// the computer's side is played by our own engine or by an external one
+pub enum Opponent {
+    Engine(engine::Engine),
+    Uci(uci::UciEngine),
+}
+
+impl Opponent {
+    pub fn get_name(&self) -> String {
+        return match self {
+            Opponent::Engine(_) => "Computer".to_string(),
+            Opponent::Uci(engine) => engine.name.clone(),
+        };
+    }
+}
+
+// a computer playing on a clock uses up its time and records it with the move
+// returns whether a move was played
+fn computer_move(game: &mut Game, opponent: &mut Opponent, limits: &mut engine::SearchLimits) -> bool {
+    let result = match opponent {
+        Opponent::Engine(engine) => engine.search(game.get_board(), limits),
+        Opponent::Uci(engine) =>
+            match engine.search(game, limits) {
+                Ok(result) => result,
+                Err(e) => {
+                    println!("Error: {e}");
+                    return false;
+                }
+            }
+    };
+    let mv = match result.best_move {
+        Some(mv) => mv,
+        None => {
+            return false;
+        }
+    };
+
+    // show the expected line in standard notation
+    let mut board = game.get_board().clone();
+    let mut line = Vec::new();
+    for pv_move in &result.pv {
+        let player = match board.get_state() {
+            GameState::Playing(p) => *p,
+            _ => {
+                break;
+            }
+        };
+        line.push(notation::to_san(&board, *pv_move));
+        if board.apply_move(player, *pv_move).is_err() {
+            break;
+        }
+    }
+
+    game.play_move(mv).unwrap();
+    if let Some(time_left) = limits.time_left {
+        let time_left = time_left.saturating_sub(result.elapsed) + limits.increment;
+        limits.time_left = Some(time_left);
+        game.set_clock(time_left);
+    }
+    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    println!("{}", game.get_board());
+    println!(
+        "{} played {} (score {}, depth {}, {} nodes in {:.1}s): {}",
+        opponent.get_name(),
+        line[0],
+        result.score,
+        result.depth,
+        result.nodes,
+        result.elapsed.as_secs_f64(),
+        line.join(" ")
+    );
+    return true;
+}
+
+fn print_continuations(game: &Game) {
+    let children = game.get_node(game.get_current()).get_children();
+    for (i, &child) in children.iter().enumerate() {
+        let san = &game.get_node(child).game_move.as_ref().unwrap().san;
+        if i == 0 {
+            println!("Next: {san}");
+        } else {
+            println!("Variation {i}: {san}");
+        }
+    }
+}
+
+// besides moves, the loop understands:
+// back, forward        step through the game
+// var <n>, return      enter a variation from the current position, go back to where it started
+// promote, delete      make the current variation the main line, remove the current move
+// comment <text>       annotate the current move
+// nag <n>              add a numeric annotation glyph to the current move
+// fen                  print the current position
+// the computer plays one side if given, searching up to the given limits
+// if the opponent stops responding, the rest of the game is left to the user
+pub fn game_loop(
+    mut game: Game,
+    mut computer: Option<Player>,
+    mut opponent: Opponent,
+    mut limits: engine::SearchLimits
+) {
+    if game.date == "????.??.??" {
+        game.date = today();
+    }
+    match computer {
+        Some(Player::White) => {
+            game.white = opponent.get_name();
+        }
+        Some(Player::Black) => {
+            game.black = opponent.get_name();
+        }
+        None => {}
+    }
+    println!("{}", game.get_board());
 
     loop {
-        if let &GameState::Playing(p) = board.get_state() {
+        // the computer only moves at the end of a line, so the game can still be navigated
+        if let GameState::Playing(p) = game.get_board().get_state() {
+            let at_end = game.get_node(game.get_current()).get_children().is_empty();
+            if computer == Some(*p) && at_end {
+                if computer_move(&mut game, &mut opponent, &mut limits) {
+                    continue;
+                }
+                computer = None;
+            }
+        }
+
+        // a variation that has ended can still be left, but the game ends with the main line
+        let p = match game.get_board().get_state() {
+            GameState::Playing(p) => *p,
+            _ if game.is_main_line() => {
+                break;
+            }
+            _ => {
+                println!("This variation is over. Enter back or return:");
+                if game.get_board().get_turn() % 2 == 0 { Player::White } else { Player::Black }
+            }
+        };
+        if let GameState::Playing(_) = game.get_board().get_state() {
             println!("({p:?}) Enter your move:");
-            let mut notation = String::new();
-            io::stdin().read_line(&mut notation).expect("failed to read line");
-            let notation = notation.trim();
+        }
+        let mut notation = String::new();
+        io::stdin().read_line(&mut notation).expect("failed to read line");
+        let notation = notation.trim();
 
-            if notation == "" {
+        let (command, argument) = notation.split_once(' ').unwrap_or((notation, ""));
+        if notation.is_empty() {
+            print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+            println!("{}", game.get_board());
+        } else if notation == "fen" {
+            println!("{}", fen::to_fen(game.get_board()));
+        } else if notation == "eval" {
+            println!("{}", evaluation::evaluate_position(game.get_board()));
+        } else if notation == "go" {
+            // let the computer make this move
+            if let GameState::Playing(_) = game.get_board().get_state() {
+                computer_move(&mut game, &mut opponent, &mut limits);
+            } else {
+                println!("Error: can't go here!");
+            }
+        } else if
+            ["back", "forward", "var", "return", "promote", "delete"].contains(&command)
+        {
+            // move around the game tree
+            let moved = match command {
+                "back" => game.back(),
+                "forward" => game.forward(),
+                "var" => game.enter_variation(argument.trim().parse().unwrap_or(0)),
+                "return" => game.exit_variation(),
+                "promote" => game.promote_variation(),
+                _ => game.delete_branch(),
+            };
+            if moved {
                 print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-                println!("{board}");
+                println!("{}", game.get_board());
+                print_continuations(&game);
             } else {
-                let result = board.execute_notation(Some(p), &notation);
-                match result {
-                    Ok(_) => {
-                        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-                        println!("{board}");
-                    }
-                    Err(e) => {
-                        println!("Error: {e}");
+                println!("Error: can't {command} here!");
+            }
+        } else if command == "comment" {
+            game.add_comment(argument.trim());
+        } else if command == "nag" {
+            match argument.trim().parse::<u8>() {
+                Ok(nag) => game.add_nag(nag),
+                Err(_) => println!("Error: a NAG is a number from 0 to 255!"),
+            }
+        } else {
+            // warn about captures that lose material once all the recaptures are played out
+            let board = game.get_board();
+            let exchange = match notation::parse_notation(board, &p, notation) {
+                Ok(mv) if board.is_capture(mv) => Some(board.see(mv)),
+                _ => None,
+            };
+
+            let result = game.play_notation(notation);
+            match result {
+                Ok(_) => {
+                    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+                    println!("{}", game.get_board());
+                    if let Some(score) = exchange.filter(|s| *s < 0) {
+                        println!("Warning: that capture loses material ({score} centipawns)!");
                     }
                 }
+                Err(e) => {
+                    println!("Error: {e}");
+                }
             }
-        } else {
-            break;
         }
     }
 
-    if let GameState::Won(p) = board.get_state() {
+    if let GameState::Won(p) = game.get_result() {
         println!("{p:?} won!");
     }
 
-    if let GameState::Draw = board.get_state() {
+    if let GameState::Draw = game.get_result() {
         println!("Game ended in a draw!");
     }
+
+    // offer to keep a record of the game
+    println!("Save the game as PGN? Enter a file name (or leave empty to skip):");
+    let mut path = String::new();
+    io::stdin().read_line(&mut path).expect("failed to read line");
+    let path = path.trim();
+    if !path.is_empty() {
+        match std::fs::write(path, pgn::write_pgn(&pgn::from_game(&game))) {
+            Ok(_) => println!("Saved game to {path}"),
+            Err(e) => println!("Error: {e}"),
+        }
+    }
 }
@@ -0,0 +1,82 @@
+#![allow(clippy::needless_return)]
+
+// chess rules engine behind the cli-chess front end
+// everything other crates may rely on is re-exported here; the modules themselves are private
+
+mod game;
+mod pieces;
+mod board;
+mod notation;
+mod moves;
+mod fen;
+mod epd;
+mod pgn;
+mod validate;
+mod normalize;
+mod dataset;
+mod database;
+mod search;
+mod evaluation;
+mod engine;
+mod transposition;
+mod uci;
+mod xboard;
+
+// the tests assert on booleans with assert_eq and wrap each file in a module of the same name
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::module_inception)]
+mod tests;
+
+pub use board::{ Board, SetupError, convert_position_1d };
+pub use database::{
+    Database,
+    DatabaseWriter,
+    StoredGame,
+    encode_move,
+    decode_move,
+    encode_game,
+    decode_game,
+    pgn_to_database,
+    database_to_pgn,
+};
+pub use dataset::{ DatasetFormat, DatasetRow, dataset_rows, export_dataset };
+pub use engine::{ DEFAULT_HASH_SIZE, Engine, MATE, MAX_DEPTH, Score, SearchLimits, SearchResult };
+pub use transposition::{ Bound, TableEntry, TranspositionTable, hash_position };
+pub use evaluation::{ Evaluation, Term, evaluate, evaluate_position };
+pub use epd::{ Epd, parse_epd, to_epd };
+pub use fen::{ STARTING_FEN, parse_fen, to_fen, parse_square, square_name };
+pub use game::{ Game, GameMove, GameNode, GameState, Opponent, Player, Termination, other_player, game_loop };
+pub use moves::{ MoveError, MoveOutcome, PieceMove };
+pub use notation::{ parse_notation, parse_uci, to_san, to_uci };
+pub use pgn::{
+    PgnError,
+    PgnErrorKind,
+    PgnGame,
+    PgnMove,
+    PgnReader,
+    read_pgn_file,
+    parse_pgn,
+    parse_pgn_games,
+    write_pgn,
+    from_game,
+    to_game,
+    result_token,
+};
+pub use pieces::{ Piece, PieceType };
+pub use normalize::{ NormalizeOptions, normalize_game, normalize_pgn };
+pub use search::{
+    Material,
+    PawnStructure,
+    PositionQuery,
+    SquarePattern,
+    SearchHit,
+    parse_material,
+    parse_piece_square,
+    parse_pawns,
+    parse_position,
+    search_pgn,
+    search_database,
+};
+pub use uci::{ UciEngine, uci_loop };
+pub use xboard::xboard_loop;
+pub use validate::{ ValidationIssue, ValidationReport, validate_game, validate_pgn };
@@ -1,12 +1,392 @@
-mod game;
-mod pieces;
-mod board;
-mod notation;
-mod moves;
+#![allow(clippy::needless_return)]
 
-#[cfg(test)]
-mod tests;
+use cli_chess::Game;
 
 fn main() {
-    game::game_loop();
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        // check every game in a PGN file
+        Some("validate") => {
+            match args.get(2) {
+                Some(path) => validate(path),
+                None => println!("Usage: cli-chess validate <file.pgn>"),
+            }
+        }
+        // rewrite a PGN file canonically
+        Some("normalize") => {
+            match args.get(2) {
+                Some(path) => normalize(path, &args[3..]),
+                None =>
+                    println!(
+                        "Usage: cli-chess normalize <file.pgn> [--strip-comments] [--strip-variations] [--strip-clocks]"
+                    ),
+            }
+        }
+        // write one row per ply for every game in a PGN file
+        Some("export") => {
+            match args.get(2) {
+                Some(path) => export(path, &args[3..]),
+                None => println!("Usage: cli-chess export <file.pgn> [--csv | --jsonl]"),
+            }
+        }
+        // convert between PGN and the binary database format, going by the file extensions
+        Some("convert") => {
+            match (args.get(2), args.get(3)) {
+                (Some(input), Some(output)) => convert(input, output),
+                _ => println!("Usage: cli-chess convert <in.pgn> <out.ccdb> | <in.ccdb> <out.pgn>"),
+            }
+        }
+        // find games reaching a position or pattern
+        Some("search") => {
+            match args.get(2) {
+                Some(path) if args.len() > 3 => search(path, &args[3..]),
+                _ =>
+                    println!(
+                        "Usage: cli-chess search <file.pgn | file.ccdb> [--fen <FEN>] [--material <KRPvKR>] [--piece <Ke1 | pd5 | d4>]... [--pawns <FEN placement>] [--side <w | b>]"
+                    ),
+            }
+        }
+        // explain the static evaluation of a position, term by term
+        Some("eval") => {
+            let board = match args.get(2) {
+                Some(position) =>
+                    match cli_chess::parse_fen(position) {
+                        Ok(board) => board,
+                        Err(e) => {
+                            println!("Error: {e}");
+                            std::process::exit(2);
+                        }
+                    }
+                None => Game::new().get_board().clone(),
+            };
+            println!("{}", cli_chess::evaluate_position(&board));
+        }
+        // run as an engine for a chess GUI
+        Some("uci") => cli_chess::uci_loop(std::io::stdin().lock(), std::io::stdout()),
+        // run as an engine for a GUI or ICS bot that speaks XBoard's protocol instead
+        Some("xboard") => cli_chess::xboard_loop(std::io::stdin().lock(), std::io::stdout()),
+        // optionally start from a custom position given as FEN, or play against the computer
+        _ => play(&args[1..]),
+    }
+}
+
+fn play(options: &[String]) {
+    let mut game = Game::new();
+    let mut computer = None;
+    let mut limits = cli_chess::SearchLimits::default();
+    let mut hash_size = None;
+    let mut threads = None;
+    let mut engine_path = None;
+    let mut depth_given = false;
+    let mut i = 0;
+    while i < options.len() {
+        match options[i].as_str() {
+            "--computer" | "--engine" | "--depth" | "--hash" | "--threads" | "--movetime" | "--clock" => {
+                let value = options.get(i + 1).map_or("", |value| value.as_str());
+                let valid = match options[i].as_str() {
+                    "--computer" => {
+                        computer = match value {
+                            "white" => Some(cli_chess::Player::White),
+                            "black" => Some(cli_chess::Player::Black),
+                            _ => None,
+                        };
+                        computer.is_some()
+                    }
+                    "--engine" => {
+                        engine_path = Some(value);
+                        !value.is_empty()
+                    }
+                    "--depth" => {
+                        match value.parse::<u32>() {
+                            Ok(depth) if depth > 0 => {
+                                limits.depth = depth;
+                                depth_given = true;
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                    "--hash" => {
+                        match value.parse::<usize>() {
+                            Ok(megabytes) => {
+                                hash_size = Some(megabytes);
+                                true
+                            }
+                            Err(_) => false,
+                        }
+                    }
+                    "--threads" => {
+                        match value.parse::<usize>() {
+                            Ok(count) if count > 0 => {
+                                threads = Some(count);
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                    "--movetime" => {
+                        limits.move_time = parse_seconds(value);
+                        limits.move_time.is_some()
+                    }
+                    // minutes for the game, optionally plus an increment in seconds
+                    _ => {
+                        let (minutes, increment) = value.split_once('+').unwrap_or((value, "0"));
+                        match (parse_seconds(minutes), parse_seconds(increment)) {
+                            (Some(minutes), Some(increment)) if !minutes.is_zero() => {
+                                limits.time_left = Some(minutes * 60);
+                                limits.increment = increment;
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                };
+                if !valid {
+                    println!(
+                        "Usage: cli-chess [FEN] [--computer <white | black>] [--engine <path>] [--depth <plies>] [--hash <MB>] [--threads <count>] [--movetime <seconds> | --clock <minutes>[+<increment>]]"
+                    );
+                    std::process::exit(2);
+                }
+                i += 2;
+            }
+            position => {
+                match cli_chess::parse_fen(position) {
+                    Ok(board) => {
+                        game = Game::from_board(board);
+                    }
+                    Err(e) => {
+                        println!("Error: {e}");
+                        return;
+                    }
+                }
+                i += 1;
+            }
+        }
+    }
+    // a timed computer searches as deep as its time allows
+    if !depth_given && (limits.move_time.is_some() || limits.time_left.is_some()) {
+        limits.depth = cli_chess::MAX_DEPTH;
+    }
+    let opponent = match engine_path {
+        // an external engine keeps its own defaults for anything not given
+        Some(path) => {
+            let options = [("Hash", hash_size), ("Threads", threads)];
+            let started = cli_chess::UciEngine::start(path, &[]).and_then(|mut engine| {
+                for (name, value) in options {
+                    if let Some(value) = value {
+                        engine.set_option(name, &value.to_string())?;
+                    }
+                }
+                Ok(engine)
+            });
+            match started {
+                Ok(engine) => cli_chess::Opponent::Uci(engine),
+                Err(e) => {
+                    println!("Error: could not start {path}: {e}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        None => {
+            let mut engine = cli_chess::Engine::with_hash_size(hash_size.unwrap_or(cli_chess::DEFAULT_HASH_SIZE));
+            engine.set_threads(threads.unwrap_or(1));
+            cli_chess::Opponent::Engine(engine)
+        }
+    };
+    cli_chess::game_loop(game, computer, opponent, limits);
+}
+
+fn parse_seconds(value: &str) -> Option<std::time::Duration> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds >= 0.0 && seconds.is_finite() => Some(std::time::Duration::from_secs_f64(seconds)),
+        _ => None,
+    }
+}
+
+fn open(path: &str) -> std::io::BufReader<std::fs::File> {
+    match std::fs::File::open(path) {
+        Ok(file) => std::io::BufReader::new(file),
+        Err(e) => {
+            println!("Error: could not open {path}: {e}");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn validate(path: &str) {
+    let reports = cli_chess::validate_pgn(open(path));
+    for report in &reports {
+        println!("{report}");
+    }
+    if reports.is_empty() {
+        println!("No problems found.");
+    } else {
+        println!("{} problem(s) found.", reports.len());
+        std::process::exit(1);
+    }
+}
+
+// the normalised games go to stdout and any games that had to be skipped are reported on stderr
+fn normalize(path: &str, flags: &[String]) {
+    let mut options = cli_chess::NormalizeOptions::default();
+    for flag in flags {
+        match flag.as_str() {
+            "--strip-comments" => {
+                options.strip_comments = true;
+            }
+            "--strip-variations" => {
+                options.strip_variations = true;
+            }
+            "--strip-clocks" => {
+                options.strip_clocks = true;
+            }
+            _ => {
+                println!("Error: unknown option {flag}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    report_skipped(cli_chess::normalize_pgn(open(path), &mut stdout, &options));
+}
+
+fn report_skipped(result: std::io::Result<Vec<cli_chess::ValidationReport>>) {
+    match result {
+        Ok(reports) => {
+            for report in &reports {
+                eprintln!("Skipped: {report}");
+            }
+            if !reports.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(2);
+        }
+    }
+}
+
+// the rows go to stdout and any games that had to be skipped are reported on stderr
+fn export(path: &str, flags: &[String]) {
+    let mut format = cli_chess::DatasetFormat::Csv;
+    for flag in flags {
+        match flag.as_str() {
+            "--csv" => {
+                format = cli_chess::DatasetFormat::Csv;
+            }
+            "--jsonl" => {
+                format = cli_chess::DatasetFormat::JsonLines;
+            }
+            _ => {
+                println!("Error: unknown option {flag}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    report_skipped(cli_chess::export_dataset(open(path), &mut stdout, format));
+}
+
+fn convert(input: &str, output: &str) {
+    let file = match std::fs::File::create(output) {
+        Ok(file) => std::io::BufWriter::new(file),
+        Err(e) => {
+            println!("Error: could not create {output}: {e}");
+            std::process::exit(2);
+        }
+    };
+    if input.ends_with(".ccdb") {
+        let mut writer = file;
+        match cli_chess::Database::open(open(input)) {
+            Ok(mut database) => report_skipped(cli_chess::database_to_pgn(&mut database, &mut writer)),
+            Err(e) => {
+                println!("Error: could not read {input}: {e}");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        report_skipped(cli_chess::pgn_to_database(open(input), file));
+    }
+}
+
+fn search(path: &str, options: &[String]) {
+    let mut query = cli_chess::PositionQuery::default();
+    let mut i = 0;
+    while i < options.len() {
+        let value = match options.get(i + 1) {
+            Some(value) => value.as_str(),
+            None => {
+                println!("Error: {} needs a value", options[i]);
+                std::process::exit(2);
+            }
+        };
+        let valid = match options[i].as_str() {
+            "--fen" => {
+                query.fen = cli_chess::parse_position(value);
+                query.fen.is_some()
+            }
+            "--material" => {
+                query.material = cli_chess::parse_material(value);
+                query.material.is_some()
+            }
+            "--piece" => {
+                match cli_chess::parse_piece_square(value) {
+                    Some(piece) => {
+                        query.pieces.push(piece);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "--pawns" => {
+                query.pawns = cli_chess::parse_pawns(value);
+                query.pawns.is_some()
+            }
+            "--side" => {
+                query.side_to_move = match value {
+                    "w" => Some(cli_chess::Player::White),
+                    "b" => Some(cli_chess::Player::Black),
+                    _ => None,
+                };
+                query.side_to_move.is_some()
+            }
+            _ => false,
+        };
+        if !valid {
+            println!("Error: invalid option {} {value}", options[i]);
+            std::process::exit(2);
+        }
+        i += 2;
+    }
+
+    let hits = if path.ends_with(".ccdb") {
+        match cli_chess::Database::open(open(path)) {
+            Ok(mut database) => cli_chess::search_database(&query, &mut database),
+            Err(e) => Err(e),
+        }
+    } else {
+        Ok(cli_chess::search_pgn(&query, open(path)))
+    };
+    match hits {
+        Ok(hits) => {
+            for hit in &hits {
+                let tag = |name: &str| {
+                    hit.tags
+                        .iter()
+                        .find(|(tag, _)| tag == name)
+                        .map_or("?", |(_, value)| value.as_str())
+                        .to_string()
+                };
+                println!("Game {}, ply {}: {} - {} ({})", hit.game, hit.ply, tag("White"), tag("Black"), tag("Event"));
+            }
+            println!("{} game(s) found.", hits.len());
+        }
+        Err(e) => {
+            println!("Error: could not read {path}: {e}");
+            std::process::exit(2);
+        }
+    }
 }
@@ -7,7 +7,7 @@ pub enum MoveOutcome {
     Win,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum MoveError {
     InvalidNotation,
     InvalidMove,
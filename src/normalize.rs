@@ -0,0 +1,156 @@
+use crate::board;
+use crate::notation;
+use crate::pgn;
+use crate::validate;
+use regex::Regex;
+use std::io::{ BufRead, Write };
+use std::sync::OnceLock;
+
+// the Seven Tag Roster, in the order the PGN standard requires
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+// what to leave out of the normalised game
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct NormalizeOptions {
+    pub strip_comments: bool,
+    pub strip_variations: bool,
+    pub strip_clocks: bool,
+}
+
+// rewrite a game canonically: SAN is regenerated from the moves played, the Seven Tag Roster
+// comes first (filled in if missing) followed by the other tags in alphabetical order
+pub fn normalize_game(
+    pgn_game: &pgn::PgnGame,
+    options: &NormalizeOptions
+) -> Result<pgn::PgnGame, validate::ValidationIssue> {
+    let board = pgn::starting_board(pgn_game.get_tag("FEN")).map_err(validate::ValidationIssue::InvalidSetup)?;
+
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for (name, default) in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => pgn_game.result.as_str(),
+            _ => pgn_game.get_tag(name).unwrap_or(default),
+        };
+        tags.push((name.to_string(), value.to_string()));
+    }
+    let mut others: Vec<(String, String)> = pgn_game.tags
+        .iter()
+        .filter(|(name, _)| !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name))
+        .cloned()
+        .collect();
+    others.sort_by(|a, b| a.0.cmp(&b.0));
+    tags.extend(others);
+
+    return Ok(pgn::PgnGame {
+        tags,
+        comments: normalize_comments(&pgn_game.comments, options),
+        moves: normalize_line(&board, &pgn_game.moves, options)?,
+        result: pgn_game.result.clone(),
+    });
+}
+
+// replay a line of moves from a position, rewriting each move and its variations
+fn normalize_line(
+    board: &board::Board,
+    moves: &[pgn::PgnMove],
+    options: &NormalizeOptions
+) -> Result<Vec<pgn::PgnMove>, validate::ValidationIssue> {
+    let mut board = board.clone();
+    let mut line = Vec::new();
+    for mv in moves {
+        // variations are alternatives to this move, so they start from the same position
+        let mut variations = Vec::new();
+        if !options.strip_variations {
+            for variation in &mv.variations {
+                variations.push(normalize_line(&board, variation, options)?);
+            }
+        }
+
+        let player = pgn::side_to_move(&board);
+        let piece_move = notation::parse_notation(&board, &player, &mv.san)
+            .map_err(|error| pgn::illegal_move(&board, &mv.san, error))?;
+        let san = notation::to_san(&board, piece_move);
+        board.make_move(None, piece_move).map_err(|error| pgn::illegal_move(&board, &mv.san, error))?;
+
+        line.push(pgn::PgnMove {
+            san,
+            nags: mv.nags.clone(),
+            starting_comments: normalize_comments(&mv.starting_comments, options),
+            comments: normalize_comments(&mv.comments, options),
+            variations,
+        });
+    }
+    return Ok(line);
+}
+
+// tidy up whitespace in comments, dropping those that end up empty
+fn normalize_comments(
+    comments: &[String],
+    options: &NormalizeOptions
+) -> Vec<String> {
+    static CLOCK_REGEX: OnceLock<Regex> = OnceLock::new();
+    let clock_regex = CLOCK_REGEX.get_or_init(|| Regex::new(r"\[%clk\s[^\]]*\]").unwrap());
+    let mut result = Vec::new();
+    for comment in comments {
+        let comment = if options.strip_clocks {
+            clock_regex.replace_all(comment, "").to_string()
+        } else {
+            comment.clone()
+        };
+        // clock annotations are kept even when ordinary comments are stripped
+        let comment = if options.strip_comments {
+            clock_regex
+                .find_iter(&comment)
+                .map(|m| m.as_str())
+                .collect::<Vec<&str>>()
+                .join(" ")
+        } else {
+            comment
+        };
+        let comment = comment.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !comment.is_empty() {
+            result.push(comment);
+        }
+    }
+    return result;
+}
+
+// normalise every game in a PGN source, writing them out separated by blank lines
+// games that can't be read or replayed are left out and reported instead
+pub fn normalize_pgn<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    options: &NormalizeOptions
+) -> std::io::Result<Vec<validate::ValidationReport>> {
+    let mut reports = Vec::new();
+    let mut first = true;
+    let mut games = pgn::PgnReader::new(reader);
+    while let Some(result) = games.next() {
+        let number = games.game_number();
+        let normalized = match result {
+            Ok(pgn_game) => normalize_game(&pgn_game, options),
+            Err(e) => Err(validate::ValidationIssue::Syntax(e)),
+        };
+        match normalized {
+            Ok(pgn_game) => {
+                if !first {
+                    writeln!(writer)?;
+                }
+                write!(writer, "{}", pgn::write_pgn(&pgn_game))?;
+                first = false;
+            }
+            Err(issue) => {
+                reports.push(validate::ValidationReport { game: number, issue });
+            }
+        }
+    }
+    return Ok(reports);
+}
@@ -1,9 +1,11 @@
 use crate::board;
+use crate::fen;
 use crate::game;
 use crate::pieces;
 use crate::moves;
 use regex::Regex;
 
+#[allow(clippy::collapsible_if)]
 fn get_piece_candidates(
     board: &board::Board,
     player: &game::Player,
@@ -39,11 +41,36 @@ fn get_piece_candidates(
     return candidates;
 }
 
+#[allow(
+    clippy::len_zero,
+    clippy::manual_map,
+    clippy::needless_borrow,
+    clippy::option_map_or_none,
+    clippy::unnecessary_map_or
+)]
 pub fn parse_notation(
     board: &board::Board,
     player: &game::Player,
     notation: &str
 ) -> Result<moves::PieceMove, moves::MoveError> {
+    // castling is represented as the king moving two squares
+    let castle_rank = match player {
+        game::Player::White => 0,
+        game::Player::Black => 7,
+    };
+    // only a king and rook that still have their castling right can castle
+    let castle = match notation.trim_end_matches(['+', '#']) {
+        "O-O" => Some((7, 6)),
+        "O-O-O" => Some((0, 2)),
+        _ => None,
+    };
+    if let Some((rook_file, dst_file)) = castle {
+        if !fen::can_castle(board, *player, castle_rank, rook_file) {
+            return Err(moves::MoveError::InvalidMove);
+        }
+        return Ok(moves::PieceMove::new(pieces::PieceType::King, 4, castle_rank, dst_file, castle_rank));
+    }
+
     let re = Regex::new(
         r"(?:(?P<piece_type>[kqrnKQRBN])?(?P<src_file>[a-h])?(?P<src_rank>[1-8])?(?P<capture>x)?(?P<dst_file>[a-h])(?P<dst_rank>[1-8])(?:=(?P<promotion>[qrbnQRBN]))?(?P<check>[+#])?)$"
     ).unwrap();
@@ -92,9 +119,6 @@ pub fn parse_notation(
             return Err(moves::MoveError::InvalidMove);
         }
         if candidates.len() > 1 {
-            for candidate in candidates {
-                println!("{}:{}", candidate.0, candidate.1);
-            }
             return Err(moves::MoveError::AmbiguousMove);
         }
 
@@ -124,6 +148,109 @@ pub fn parse_notation(
     }
 }
 
+// write a move in standard algebraic notation, as played from the given position
+// pieces are only disambiguated when another piece of the same type could make the move
+pub fn to_san(board: &board::Board, mv: moves::PieceMove) -> String {
+    let piece = &board.squares[board::convert_position_1d(mv.src_file, mv.src_rank)];
+    let player = match piece.get_player() {
+        Some(p) => p,
+        None => {
+            return String::new();
+        }
+    };
+    let piece_type = piece.get_type();
+
+    let mut san = String::new();
+    if board::is_castle(piece_type, mv) {
+        san += if mv.dst_file > mv.src_file { "O-O" } else { "O-O-O" };
+    } else {
+        let capture = board.is_capture(mv);
+        if piece_type == pieces::PieceType::Pawn {
+            if capture {
+                san.push((b'a' + (mv.src_file as u8)) as char);
+            }
+        } else {
+            san.push(piece_type.to_char());
+
+            let others: Vec<(i8, i8)> = get_piece_candidates(
+                board,
+                &player,
+                &piece_type,
+                None,
+                None,
+                mv.dst_file,
+                mv.dst_rank
+            )
+                .into_iter()
+                .filter(|c| *c != (mv.src_file, mv.src_rank))
+                .collect();
+            if !others.is_empty() {
+                let shares_file = others.iter().any(|c| c.0 == mv.src_file);
+                let shares_rank = others.iter().any(|c| c.1 == mv.src_rank);
+                if !shares_file {
+                    san.push((b'a' + (mv.src_file as u8)) as char);
+                } else if !shares_rank {
+                    san += &(mv.src_rank + 1).to_string();
+                } else {
+                    san += &fen::square_name(mv.src_file, mv.src_rank);
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san += &fen::square_name(mv.dst_file, mv.dst_rank);
+        if piece_type == pieces::PieceType::Pawn && mv.piece_type != pieces::PieceType::Pawn {
+            san.push('=');
+            san.push(mv.piece_type.to_char());
+        }
+    }
+
+    // play the move out to see if it checks or mates
+    let mut after = board.clone();
+    if after.make_move(Some(player), mv).is_ok() {
+        if let game::GameState::Won(_) = after.get_state() {
+            san.push('#');
+        } else if after.is_in_check(game::other_player(player)) {
+            san.push('+');
+        }
+    }
+    return san;
+}
+
+// write a move in the coordinate notation used by UCI (e.g. e2e4, or e7e8q for a promotion)
+// castling is written as the king moving two squares
+pub fn to_uci(board: &board::Board, mv: moves::PieceMove) -> String {
+    let mut uci = fen::square_name(mv.src_file, mv.src_rank) + &fen::square_name(mv.dst_file, mv.dst_rank);
+    let piece = &board.squares[board::convert_position_1d(mv.src_file, mv.src_rank)];
+    if piece.get_type() == pieces::PieceType::Pawn && mv.piece_type != pieces::PieceType::Pawn {
+        uci.push(mv.piece_type.to_char().to_ascii_lowercase());
+    }
+    return uci;
+}
+
+// read a move in UCI coordinate notation, which must be legal for the side to move
+pub fn parse_uci(board: &board::Board, uci: &str) -> Result<moves::PieceMove, moves::MoveError> {
+    if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+        return Err(moves::MoveError::InvalidNotation);
+    }
+    let (src_file, src_rank) = fen::parse_square(&uci[0..2]).ok_or(moves::MoveError::InvalidNotation)?;
+    let (dst_file, dst_rank) = fen::parse_square(&uci[2..4]).ok_or(moves::MoveError::InvalidNotation)?;
+    let piece_type = match uci.chars().nth(4) {
+        Some(c @ ('q' | 'r' | 'b' | 'n')) => pieces::PieceType::from_char(c.to_ascii_uppercase())?,
+        Some(_) => {
+            return Err(moves::MoveError::InvalidNotation);
+        }
+        None => board.squares[board::convert_position_1d(src_file, src_rank)].get_type(),
+    };
+
+    let mv = moves::PieceMove::new(piece_type, src_file, src_rank, dst_file, dst_rank);
+    if !board.legal_moves().contains(&mv) {
+        return Err(moves::MoveError::InvalidMove);
+    }
+    return Ok(mv);
+}
+
 // convert a file (a-h) to an integer (0-8)
 fn convert_file(c: char) -> i8 {
     ((c as u8) - b'a') as i8
@@ -0,0 +1,1013 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::OnceLock;
+use std::time::Duration;
+use regex::Regex;
+use crate::board;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::validate;
+
+// a move in PGN movetext, along with its annotations and any alternatives to it
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PgnMove {
+    pub san: String,
+    // numeric annotation glyphs; suffixes like !? are converted to their NAG
+    pub nags: Vec<u8>,
+    // comments written before the move (only at the start of a variation)
+    pub starting_comments: Vec<String>,
+    // comments written after the move
+    pub comments: Vec<String>,
+    // lines that could have been played instead of this move
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    // comments written before the first move
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    // the game termination marker: 1-0, 0-1, 1/2-1/2 or *
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        return self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    // the SAN of every move in the main line
+    pub fn main_line(&self) -> Vec<&str> {
+        return self.moves
+            .iter()
+            .map(|mv| mv.san.as_str())
+            .collect();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnterminatedString,
+    UnterminatedComment,
+    UnterminatedVariation,
+    UnexpectedEnd,
+    InvalidNag,
+    Io(String),
+}
+
+// an error along with the (1-based) line and column it was found at
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+impl std::fmt::Display for PgnErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PgnErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character '{c}'"),
+            PgnErrorKind::UnexpectedToken(t) => write!(f, "Unexpected '{t}'"),
+            PgnErrorKind::UnterminatedString => write!(f, "Unterminated string"),
+            PgnErrorKind::UnterminatedComment => write!(f, "Unterminated {{ comment"),
+            PgnErrorKind::UnterminatedVariation => write!(f, "Unterminated ( variation"),
+            PgnErrorKind::UnexpectedEnd => write!(f, "Unexpected end of game, expected a result"),
+            PgnErrorKind::InvalidNag => write!(f, "Invalid numeric annotation glyph"),
+            PgnErrorKind::Io(e) => write!(f, "Could not read input: {e}"),
+        }
+    }
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    TagOpen,
+    TagClose,
+    Str(String),
+    Comment(String),
+    VariationOpen,
+    VariationClose,
+    Nag(u8),
+    Result(String),
+    MoveNumber,
+    // a move, tag name or anything else made of symbol characters
+    Symbol(String),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        return Some(c);
+    }
+
+    fn error(&self, line: usize, column: usize, kind: PgnErrorKind) -> PgnError {
+        return PgnError {
+            line,
+            column,
+            kind,
+        };
+    }
+
+    // returns the next token with the line and column it starts at
+    fn next_token(&mut self) -> Result<Option<(Token, usize, usize)>, PgnError> {
+        loop {
+            let (line, column) = (self.line, self.column);
+            let c = match self.chars.peek() {
+                Some(c) => *c,
+                None => {
+                    return Ok(None);
+                }
+            };
+
+            // a % in the first column escapes the rest of the line
+            if c == '%' && column == 1 {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if c.is_whitespace() {
+                self.bump();
+                continue;
+            }
+
+            let token = match c {
+                '[' => {
+                    self.bump();
+                    Token::TagOpen
+                }
+                ']' => {
+                    self.bump();
+                    Token::TagClose
+                }
+                '(' => {
+                    self.bump();
+                    Token::VariationOpen
+                }
+                ')' => {
+                    self.bump();
+                    Token::VariationClose
+                }
+                '*' => {
+                    self.bump();
+                    Token::Result("*".to_string())
+                }
+                '"' => Token::Str(self.read_string(line, column)?),
+                '{' => {
+                    self.bump();
+                    let mut comment = String::new();
+                    loop {
+                        match self.bump() {
+                            Some('}') => {
+                                break;
+                            }
+                            Some(c) => comment.push(c),
+                            None => {
+                                return Err(
+                                    self.error(line, column, PgnErrorKind::UnterminatedComment)
+                                );
+                            }
+                        }
+                    }
+                    Token::Comment(comment.trim().to_string())
+                }
+                ';' => {
+                    self.bump();
+                    let mut comment = String::new();
+                    while let Some(c) = self.chars.peek() {
+                        if *c == '\n' {
+                            break;
+                        }
+                        comment.push(*c);
+                        self.bump();
+                    }
+                    Token::Comment(comment.trim().to_string())
+                }
+                '$' => {
+                    self.bump();
+                    let digits = self.read_while(|c| c.is_ascii_digit());
+                    match digits.parse::<u8>() {
+                        Ok(n) => Token::Nag(n),
+                        Err(_) => {
+                            return Err(self.error(line, column, PgnErrorKind::InvalidNag));
+                        }
+                    }
+                }
+                '!' | '?' => {
+                    let glyph = self.read_while(|c| c == '!' || c == '?');
+                    match glyph_to_nag(&glyph) {
+                        Some(n) => Token::Nag(n),
+                        None => {
+                            return Err(
+                                self.error(line, column, PgnErrorKind::UnexpectedToken(glyph))
+                            );
+                        }
+                    }
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let symbol = self.read_while(|c| {
+                        c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+                    });
+                    if ["1-0", "0-1", "1/2-1/2"].contains(&symbol.as_str()) {
+                        Token::Result(symbol)
+                    } else if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        // move numbers are followed by one or more periods
+                        self.read_while(|c| c == '.');
+                        Token::MoveNumber
+                    } else {
+                        Token::Symbol(symbol)
+                    }
+                }
+                '.' => {
+                    self.read_while(|c| c == '.');
+                    continue;
+                }
+                _ => {
+                    return Err(self.error(line, column, PgnErrorKind::UnexpectedCharacter(c)));
+                }
+            };
+            return Ok(Some((token, line, column)));
+        }
+    }
+
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !predicate(*c) {
+                break;
+            }
+            s.push(*c);
+            self.bump();
+        }
+        return s;
+    }
+
+    // read a quoted string, handling \" and \\ escapes
+    fn read_string(&mut self, line: usize, column: usize) -> Result<String, PgnError> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => {
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    match self.bump() {
+                        Some(c) => s.push(c),
+                        None => {
+                            return Err(self.error(line, column, PgnErrorKind::UnterminatedString));
+                        }
+                    }
+                }
+                Some('\n') | None => {
+                    return Err(self.error(line, column, PgnErrorKind::UnterminatedString));
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+}
+
+// convert a move suffix annotation to its numeric annotation glyph
+fn glyph_to_nag(glyph: &str) -> Option<u8> {
+    match glyph {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, usize, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Result<Option<&(Token, usize, usize)>, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token()?;
+        }
+        return Ok(self.peeked.as_ref());
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, usize, usize)>, PgnError> {
+        if self.peeked.is_some() {
+            return Ok(self.peeked.take());
+        }
+        return self.lexer.next_token();
+    }
+
+    // the position of the end of the input, for errors
+    fn end_error(&self, kind: PgnErrorKind) -> PgnError {
+        return self.lexer.error(self.lexer.line, self.lexer.column, kind);
+    }
+
+    fn unexpected(token: Token, line: usize, column: usize) -> PgnError {
+        let text = match token {
+            Token::TagOpen => "[".to_string(),
+            Token::TagClose => "]".to_string(),
+            Token::Str(s) => format!("\"{s}\""),
+            Token::Comment(c) => format!("{{{c}}}"),
+            Token::VariationOpen => "(".to_string(),
+            Token::VariationClose => ")".to_string(),
+            Token::Nag(n) => format!("${n}"),
+            Token::Result(r) => r,
+            Token::MoveNumber => "move number".to_string(),
+            Token::Symbol(s) => s,
+        };
+        return PgnError {
+            line,
+            column,
+            kind: PgnErrorKind::UnexpectedToken(text),
+        };
+    }
+
+    // parse the next game, or return None if there are no more
+    fn parse_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+        if self.peek()?.is_none() {
+            return Ok(None);
+        }
+
+        let mut game = PgnGame::default();
+
+        // tag pairs: [Name "value"]
+        while let Some((Token::TagOpen, _, _)) = self.peek()? {
+            self.next()?;
+            let name = match self.next()? {
+                Some((Token::Symbol(name), _, _)) => name,
+                Some((t, l, c)) => {
+                    return Err(Parser::unexpected(t, l, c));
+                }
+                None => {
+                    return Err(self.end_error(PgnErrorKind::UnexpectedEnd));
+                }
+            };
+            let value = match self.next()? {
+                Some((Token::Str(value), _, _)) => value,
+                Some((t, l, c)) => {
+                    return Err(Parser::unexpected(t, l, c));
+                }
+                None => {
+                    return Err(self.end_error(PgnErrorKind::UnexpectedEnd));
+                }
+            };
+            match self.next()? {
+                Some((Token::TagClose, _, _)) => {}
+                Some((t, l, c)) => {
+                    return Err(Parser::unexpected(t, l, c));
+                }
+                None => {
+                    return Err(self.end_error(PgnErrorKind::UnexpectedEnd));
+                }
+            }
+            game.tags.push((name, value));
+        }
+
+        let (moves, comments, end) = self.parse_line(false)?;
+        game.moves = moves;
+        game.comments = comments;
+        game.result = end.unwrap_or_default();
+        return Ok(Some(game));
+    }
+
+    // parse moves until the end of a variation or the game result
+    // returns the moves, any comments before the first move, and the result (main line only)
+    #[allow(clippy::type_complexity)]
+    fn parse_line(
+        &mut self,
+        variation: bool
+    ) -> Result<(Vec<PgnMove>, Vec<String>, Option<String>), PgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut starting_comments: Vec<String> = Vec::new();
+
+        loop {
+            let (token, line, column) = match self.next()? {
+                Some(t) => t,
+                None => {
+                    let kind = if variation {
+                        PgnErrorKind::UnterminatedVariation
+                    } else {
+                        PgnErrorKind::UnexpectedEnd
+                    };
+                    return Err(self.end_error(kind));
+                }
+            };
+
+            match token {
+                Token::MoveNumber => {}
+                Token::Symbol(san) => {
+                    let mut mv = PgnMove {
+                        san,
+                        ..PgnMove::default()
+                    };
+                    if moves.is_empty() && variation {
+                        mv.starting_comments = std::mem::take(&mut starting_comments);
+                    }
+                    moves.push(mv);
+                }
+                Token::Comment(comment) => {
+                    match moves.last_mut() {
+                        Some(mv) => mv.comments.push(comment),
+                        None => starting_comments.push(comment),
+                    }
+                }
+                Token::Nag(nag) => {
+                    match moves.last_mut() {
+                        Some(mv) => mv.nags.push(nag),
+                        None => {
+                            return Err(Parser::unexpected(Token::Nag(nag), line, column));
+                        }
+                    }
+                }
+                Token::VariationOpen => {
+                    let (variation_moves, _, _) = self.parse_line(true)?;
+                    match moves.last_mut() {
+                        Some(mv) => mv.variations.push(variation_moves),
+                        None => {
+                            return Err(Parser::unexpected(Token::VariationOpen, line, column));
+                        }
+                    }
+                }
+                Token::VariationClose if variation => {
+                    return Ok((moves, starting_comments, None));
+                }
+                Token::Result(result) if !variation => {
+                    return Ok((moves, starting_comments, Some(result)));
+                }
+                t => {
+                    return Err(Parser::unexpected(t, line, column));
+                }
+            }
+        }
+    }
+}
+
+// parse a single game
+pub fn parse_pgn(text: &str) -> Result<PgnGame, PgnError> {
+    let mut parser = Parser {
+        lexer: Lexer::new(text),
+        peeked: None,
+    };
+    let game = match parser.parse_game()? {
+        Some(game) => game,
+        None => {
+            return Err(parser.end_error(PgnErrorKind::UnexpectedEnd));
+        }
+    };
+    if let Some((t, l, c)) = parser.next()? {
+        return Err(Parser::unexpected(t, l, c));
+    }
+    return Ok(game);
+}
+
+// parse every game in a text
+pub fn parse_pgn_games(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut parser = Parser {
+        lexer: Lexer::new(text),
+        peeked: None,
+    };
+    let mut games: Vec<PgnGame> = Vec::new();
+    while let Some(game) = parser.parse_game()? {
+        games.push(game);
+    }
+    return Ok(games);
+}
+
+// reads games one at a time from a (possibly huge) multi-game PGN source
+// each game is parsed on its own, so an error only affects the game it occurs in
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    // number of lines read so far
+    line: usize,
+    // text of the game currently being read, and the line it started on
+    chunk: String,
+    chunk_start: usize,
+    in_comment: bool,
+    // how many variations the movetext is inside of
+    depth: usize,
+    has_movetext: bool,
+    // games queued so far, and the number of the last one returned
+    games: usize,
+    game: usize,
+    pending: VecDeque<(usize, Result<PgnGame, PgnError>)>,
+    done: bool,
+    tag_regex: Regex,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            reader,
+            line: 0,
+            chunk: String::new(),
+            chunk_start: 1,
+            in_comment: false,
+            depth: 0,
+            has_movetext: false,
+            games: 0,
+            game: 0,
+            pending: VecDeque::new(),
+            done: false,
+            tag_regex: Regex::new(r#"^\[[A-Za-z0-9_]+\s+".*"\]\s*$"#).unwrap(),
+        }
+    }
+
+    // the number of the game last returned, counting from 1
+    // a game that couldn't be read still has a number, so the ones after it keep theirs
+    pub fn game_number(&self) -> usize {
+        return self.game;
+    }
+
+    // parse the buffered text and queue the games (or error) found in it
+    fn flush(&mut self) {
+        if !self.chunk.trim().is_empty() {
+            match parse_pgn_games(&self.chunk) {
+                Ok(games) => {
+                    for game in games {
+                        self.games += 1;
+                        self.pending.push_back((self.games, Ok(game)));
+                    }
+                }
+                Err(mut e) => {
+                    e.line += self.chunk_start - 1;
+                    self.games += 1;
+                    self.pending.push_back((self.games, Err(e)));
+                }
+            }
+        }
+        self.chunk.clear();
+        self.in_comment = false;
+        self.depth = 0;
+        self.has_movetext = false;
+    }
+
+    fn process_line(&mut self, line: &str) {
+        let line = line.trim_start_matches('\u{feff}');
+        let is_tag = line.starts_with('[');
+
+        // a tag after the movetext starts the next game
+        // a tag pair inside a comment most likely means the comment was never closed
+        if is_tag && self.has_movetext && (!self.in_comment || self.tag_regex.is_match(line)) {
+            self.flush();
+        }
+        if self.chunk.is_empty() {
+            self.chunk_start = self.line;
+        }
+        if is_tag && !self.in_comment {
+            self.chunk += line;
+            return;
+        }
+
+        // a result outside of any variation ends the game, even when no tags follow it
+        let mut rest = line;
+        while let Some(end) = self.scan_movetext(rest) {
+            self.chunk += &rest[..end];
+            self.flush();
+            // the next game starts further along the same line; pad it so error columns still match
+            rest = &rest[end..];
+            self.chunk_start = self.line;
+            self.chunk = " ".repeat(line[..line.len() - rest.len()].chars().count());
+        }
+        self.chunk += rest;
+    }
+
+    // follow comments and variations through some movetext, so braces inside comments aren't mistaken for tags
+    // returns where the game ends if a result outside of any variation is found
+    fn scan_movetext(&mut self, text: &str) -> Option<usize> {
+        let mut symbol_start = None;
+        for (i, c) in text.char_indices() {
+            if self.in_comment {
+                if c == '}' {
+                    self.in_comment = false;
+                }
+                continue;
+            }
+            // symbols are read as the lexer reads them, and 1-0, 0-1 and 1/2-1/2 are symbols
+            if c.is_ascii_alphanumeric() || "_+#=:-/".contains(c) {
+                symbol_start.get_or_insert(i);
+                self.has_movetext = true;
+                continue;
+            }
+            if let Some(start) = symbol_start.take() {
+                if self.depth == 0 && ["1-0", "0-1", "1/2-1/2"].contains(&&text[start..i]) {
+                    return Some(i);
+                }
+            }
+            match c {
+                '{' => {
+                    self.in_comment = true;
+                }
+                ';' => {
+                    return None;
+                }
+                '%' if !self.has_movetext => {
+                    return None;
+                }
+                '*' if self.depth == 0 => {
+                    return Some(i + 1);
+                }
+                '(' => {
+                    self.depth += 1;
+                    self.has_movetext = true;
+                }
+                ')' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.has_movetext = true;
+                }
+                c if !c.is_whitespace() => {
+                    self.has_movetext = true;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = symbol_start {
+            if self.depth == 0 && ["1-0", "0-1", "1/2-1/2"].contains(&&text[start..]) {
+                return Some(text.len());
+            }
+        }
+        return None;
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((game, item)) = self.pending.pop_front() {
+                self.game = game;
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => {
+                    self.flush();
+                    self.done = true;
+                }
+                Ok(_) => {
+                    self.line += 1;
+                    self.process_line(&line);
+                }
+                Err(e) => {
+                    self.flush();
+                    self.games += 1;
+                    self.pending.push_back((
+                        self.games,
+                        Err(PgnError {
+                            line: self.line + 1,
+                            column: 1,
+                            kind: PgnErrorKind::Io(e.to_string()),
+                        }),
+                    ));
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+// stream the games in a PGN file
+pub fn read_pgn_file(
+    path: &str
+) -> std::io::Result<PgnReader<std::io::BufReader<std::fs::File>>> {
+    let file = std::fs::File::open(path)?;
+    return Ok(PgnReader::new(std::io::BufReader::new(file)));
+}
+
+// the result token for a game state
+pub fn result_token(state: &game::GameState) -> &'static str {
+    match state {
+        game::GameState::Won(game::Player::White) => "1-0",
+        game::GameState::Won(game::Player::Black) => "0-1",
+        game::GameState::Draw => "1/2-1/2",
+        game::GameState::Playing(_) => "*",
+    }
+}
+
+// describe a played game in PGN terms
+// variations become RAVs and clock times are kept as [%clk] comments
+pub fn from_game(game: &game::Game) -> PgnGame {
+    let result = result_token(game.get_result()).to_string();
+    let mut tags: Vec<(String, String)> = vec![
+        ("Event".to_string(), game.event.clone()),
+        ("Site".to_string(), game.site.clone()),
+        ("Date".to_string(), game.date.clone()),
+        ("Round".to_string(), game.round.clone()),
+        ("White".to_string(), game.white.clone()),
+        ("Black".to_string(), game.black.clone()),
+        ("Result".to_string(), result.clone())
+    ];
+
+    // games that don't start from the initial position must say where they start
+    let start = fen::to_fen(game.get_node(0).get_board());
+    if start != fen::STARTING_FEN {
+        tags.push(("SetUp".to_string(), "1".to_string()));
+        tags.push(("FEN".to_string(), start));
+    }
+
+    return PgnGame {
+        tags,
+        comments: game.get_node(0).comments.clone(),
+        moves: export_line(game, 0),
+        result,
+    };
+}
+
+// the moves following a node, with the alternatives to each move as variations
+fn export_line(game: &game::Game, mut node: usize) -> Vec<PgnMove> {
+    let mut line = Vec::new();
+    while let Some((&main, alternatives)) = game.get_node(node).get_children().split_first() {
+        let mut mv = export_move(game.get_node(main));
+        for &alternative in alternatives {
+            let mut variation = vec![export_move(game.get_node(alternative))];
+            variation.extend(export_line(game, alternative));
+            mv.variations.push(variation);
+        }
+        line.push(mv);
+        node = main;
+    }
+    return line;
+}
+
+fn export_move(node: &game::GameNode) -> PgnMove {
+    let game_move = node.game_move.as_ref().unwrap();
+    // the clock goes at the front of the first comment
+    let mut comments = node.comments.clone();
+    if let Some(clock) = game_move.clock {
+        let clock = format!("[%clk {}]", format_clock(clock));
+        match comments.first_mut() {
+            Some(first) => {
+                *first = format!("{clock} {first}");
+            }
+            None => comments.push(clock),
+        }
+    }
+    return PgnMove {
+        san: game_move.san.clone(),
+        nags: node.nags.clone(),
+        starting_comments: node.starting_comments.clone(),
+        comments,
+        variations: Vec::new(),
+    };
+}
+
+// the position a game starts from: the one in its FEN tag, or the standard starting position
+pub(crate) fn starting_board(fen_tag: Option<&str>) -> Result<board::Board, board::SetupError> {
+    match fen_tag {
+        Some(position) => fen::parse_fen(position),
+        None => {
+            let mut board = board::Board::new();
+            board.reset_board();
+            return Ok(board);
+        }
+    }
+}
+
+// the player whose turn it is, even once the game is over
+pub(crate) fn side_to_move(board: &board::Board) -> game::Player {
+    return if board.get_turn() % 2 == 0 { game::Player::White } else { game::Player::Black };
+}
+
+// report a move that can't be played from this position
+pub(crate) fn illegal_move(board: &board::Board, san: &str, error: moves::MoveError) -> validate::ValidationIssue {
+    return validate::ValidationIssue::IllegalMove {
+        move_number: board.get_turn() / 2 + 1,
+        player: side_to_move(board),
+        san: san.to_string(),
+        error,
+    };
+}
+
+// rebuild a game, variations included, from PGN
+// the current node is left at the end of the main line
+pub fn to_game(pgn_game: &PgnGame) -> Result<game::Game, validate::ValidationIssue> {
+    let board = starting_board(pgn_game.get_tag("FEN")).map_err(validate::ValidationIssue::InvalidSetup)?;
+    let mut game = game::Game::from_board(board);
+    let tag = |name: &str, default: &str| pgn_game.get_tag(name).unwrap_or(default).to_string();
+    game.event = tag("Event", "?");
+    game.site = tag("Site", "?");
+    game.date = tag("Date", "????.??.??");
+    game.round = tag("Round", "?");
+    game.white = tag("White", "?");
+    game.black = tag("Black", "?");
+
+    for comment in &pgn_game.comments {
+        game.add_comment(comment);
+    }
+    import_line(&mut game, &pgn_game.moves)?;
+
+    // a decisive result that wasn't reached on the board was a resignation or agreement
+    // anything else, * or no result at all, leaves the game unfinished
+    if let game::GameState::Playing(_) = game.get_board().get_state() {
+        match pgn_game.result.as_str() {
+            "1-0" => game.resign(game::Player::Black),
+            "0-1" => game.resign(game::Player::White),
+            "1/2-1/2" => game.agree_draw(),
+            _ => {}
+        }
+    }
+    return Ok(game);
+}
+
+// clock annotations in h:mm:ss form, compiled on first use
+fn clock_regex() -> &'static Regex {
+    static CLOCK_REGEX: OnceLock<Regex> = OnceLock::new();
+    return CLOCK_REGEX.get_or_init(|| Regex::new(r"\[%clk\s+(\d+):(\d+):(\d+)\]").unwrap());
+}
+
+fn import_line(
+    game: &mut game::Game,
+    moves: &[PgnMove]
+) -> Result<(), validate::ValidationIssue> {
+    for mv in moves {
+        let branch = game.get_current();
+        if let Err(error) = game.play_notation(&mv.san) {
+            return Err(illegal_move(game.get_board(), &mv.san, error));
+        }
+
+        for &nag in &mv.nags {
+            game.add_nag(nag);
+        }
+        let node = game.get_current();
+        for comment in &mv.starting_comments {
+            game.add_starting_comment(comment);
+        }
+        for comment in &mv.comments {
+            // clock annotations are turned back into clock times
+            if let Some(caps) = clock_regex().captures(comment) {
+                let part = |i: usize| caps[i].parse::<u64>().unwrap_or(0);
+                game.set_clock(Duration::from_secs(part(1) * 3600 + part(2) * 60 + part(3)));
+            }
+            let comment = clock_regex().replace_all(comment, "");
+            if !comment.trim().is_empty() {
+                game.add_comment(comment.trim());
+            }
+        }
+
+        for variation in &mv.variations {
+            game.go_to(branch);
+            import_line(game, variation)?;
+            game.go_to(node);
+        }
+    }
+    return Ok(());
+}
+
+// format a clock time as h:mm:ss
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    return format!("{}:{:02}:{:02}", seconds / 3600, (seconds / 60) % 60, seconds % 60);
+}
+
+// write a game in PGN export format, with movetext wrapped at 80 columns
+pub fn write_pgn(game: &PgnGame) -> String {
+    let mut pgn = String::new();
+    for (name, value) in &game.tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn += &format!("[{name} \"{value}\"]\n");
+    }
+    pgn.push('\n');
+
+    let mut tokens: Vec<String> = Vec::new();
+    for comment in &game.comments {
+        push_comment(&mut tokens, comment);
+    }
+    let (number, black) = starting_move(game);
+    push_line(&mut tokens, &game.moves, number, black);
+    tokens.push(game.result.clone());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+            pgn += &line;
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += &token;
+    }
+    pgn += &line;
+    pgn.push('\n');
+    return pgn;
+}
+
+// the move number and side to move of the first move, taken from the FEN tag
+fn starting_move(game: &PgnGame) -> (u32, bool) {
+    if let Some(position) = game.get_tag("FEN") {
+        let fields: Vec<&str> = position.split_whitespace().collect();
+        let black = fields.get(1) == Some(&"b");
+        let number = fields
+            .get(5)
+            .and_then(|n| n.parse::<u32>().ok())
+            .unwrap_or(1);
+        return (number, black);
+    }
+    return (1, false);
+}
+
+// comments are split into words so that long comments can be wrapped
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    if words.is_empty() {
+        tokens.push("{}".to_string());
+        return;
+    }
+    for (i, word) in words.iter().enumerate() {
+        let mut token = word.to_string();
+        if i == 0 {
+            token = format!("{{{token}");
+        }
+        if i == words.len() - 1 {
+            token.push('}');
+        }
+        tokens.push(token);
+    }
+}
+
+fn push_line(tokens: &mut Vec<String>, moves: &[PgnMove], mut number: u32, mut black: bool) {
+    // black moves only need a number at the start of a line or after an interruption
+    let mut needs_number = true;
+    for mv in moves {
+        for comment in &mv.starting_comments {
+            push_comment(tokens, comment);
+        }
+        if !black {
+            tokens.push(format!("{number}."));
+        } else if needs_number {
+            tokens.push(format!("{number}..."));
+        }
+        tokens.push(mv.san.clone());
+        needs_number = false;
+
+        for nag in &mv.nags {
+            tokens.push(format!("${nag}"));
+        }
+        for comment in &mv.comments {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+        for variation in &mv.variations {
+            let mut variation_tokens: Vec<String> = Vec::new();
+            push_line(&mut variation_tokens, variation, number, black);
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+            tokens.append(&mut variation_tokens);
+            needs_number = true;
+        }
+
+        if black {
+            number += 1;
+        }
+        black = !black;
+    }
+}
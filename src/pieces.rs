@@ -35,9 +35,37 @@ impl PieceType {
             _ => Ok(PieceType::Pawn),
         }
     }
+
+    // the letter used for the piece in notation (P for pawn)
+    pub fn to_char(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Rook => 'R',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Pawn => 'P',
+            PieceType::Empty => ' ',
+        }
+    }
+
+    // material value in centipawns
+    // the king can never actually be traded, so it is simply worth more than everything else
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::Empty => 0,
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 20000,
+        }
+    }
 }
 
-pub trait Piece: DynClone + std::fmt::Display {
+// pieces are plain data, so boards can be shared between search threads
+pub trait Piece: DynClone + std::fmt::Display + Send + Sync {
     fn get_player(&self) -> Option<game::Player>;
     fn get_type(&self) -> PieceType;
     fn can_attack(&self, board: &board::Board, file: i8, rank: i8) -> bool;
@@ -32,6 +32,7 @@ impl pieces::Piece for Pawn {
         return pieces::PieceType::Pawn;
     }
 
+    #[allow(clippy::manual_range_contains)]
     fn can_attack(&self, _: &board::Board, file: i8, rank: i8) -> bool {
         if file == self.data.file - 1 || file == self.data.file + 1 {
             // the rank that the pawn can reach and attack
@@ -61,12 +62,17 @@ impl pieces::Piece for Pawn {
             }
 
             // allow one one square forwards
-            // first move allow two squares forwards
+            // from the starting rank allow two squares forwards, if the square passed is empty
             let diff: i8 = (rank - self.data.rank) * self.get_direction_coeff();
-            return match self.get_last_move() {
-                None => diff == 1 || diff == 2,
-                Some(_) => diff == 1,
+            let start_rank = match self.data.player {
+                game::Player::White => 1,
+                game::Player::Black => 6,
             };
+            if diff == 2 && self.data.rank == start_rank {
+                let passed = board::convert_position_1d(file, self.data.rank + self.get_direction_coeff());
+                return board.squares[passed].get_type() == pieces::PieceType::Empty;
+            }
+            return diff == 1;
         } else if self.can_attack(board, file, rank) {
             // pawn move not within same file (capture move)
             if target.get_type() != pieces::PieceType::Empty {
@@ -0,0 +1,288 @@
+use crate::board;
+use crate::database;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::notation;
+use crate::pgn;
+use crate::pieces;
+use std::io::{ BufRead, Read, Seek };
+
+// the number of pieces of each type a side has, indexed by PieceType
+pub type Material = [u8; 7];
+
+// a square and the piece that must stand on it (None for an empty square)
+pub type SquarePattern = (i8, i8, Option<(game::Player, pieces::PieceType)>);
+
+// the squares of white's and black's pawns, sorted
+pub type PawnStructure = (Vec<(i8, i8)>, Vec<(i8, i8)>);
+
+// what a position must look like to match; every given condition has to hold
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PositionQuery {
+    // the first four FEN fields (placement, side to move, castling, en passant) must be equal
+    // the FEN is compared as to_fen writes it, so give it through parse_position
+    pub fen: Option<String>,
+    // squares that must hold a given piece, or be empty (None)
+    pub pieces: Vec<SquarePattern>,
+    // the exact material of white and black
+    pub material: Option<(Material, Material)>,
+    // the exact squares of white's and black's pawns
+    pub pawns: Option<PawnStructure>,
+    pub side_to_move: Option<game::Player>,
+}
+
+// a game reaching the position, at the first ply it does so (0 for the starting position)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    // 1-based number of the game in the file
+    pub game: usize,
+    pub ply: usize,
+    pub tags: Vec<(String, String)>,
+}
+
+// parse a material signature such as KRPvKR (white's pieces, then black's)
+pub fn parse_material(signature: &str) -> Option<(Material, Material)> {
+    let (white, black) = signature.split_once(['v', 'V'])?;
+    let count = |side: &str| -> Option<Material> {
+        let mut material = [0; 7];
+        for c in side.chars() {
+            if !"KQRBNP".contains(c) {
+                return None;
+            }
+            material[pieces::PieceType::from_char(c).ok()? as usize] += 1;
+        }
+        return Some(material);
+    };
+    return Some((count(white)?, count(black)?));
+}
+
+// parse a piece on a square, such as Ke1 for a white king or pd5 for a black pawn
+// a square on its own (e.g. d4) must be empty
+pub fn parse_piece_square(text: &str) -> Option<SquarePattern> {
+    if text.len() == 2 {
+        let (file, rank) = fen::parse_square(text)?;
+        return Some((file, rank, None));
+    }
+    let c = text.chars().next()?;
+    if !"KQRBNP".contains(c.to_ascii_uppercase()) {
+        return None;
+    }
+    let (file, rank) = fen::parse_square(&text[1..])?;
+    let player = if c.is_ascii_uppercase() { game::Player::White } else { game::Player::Black };
+    let piece_type = pieces::PieceType::from_char(c.to_ascii_uppercase()).ok()?;
+    return Some((file, rank, Some((player, piece_type))));
+}
+
+// parse a FEN into the form positions are compared in
+// to_fen only gives an en passant square when a capture is possible there, as most FENs don't
+pub fn parse_position(position: &str) -> Option<String> {
+    return fen::parse_fen(position).ok().map(|board| fen::to_fen(&board));
+}
+
+// parse a pawn structure from the placement field of a FEN; any other pieces are ignored
+pub fn parse_pawns(placement: &str) -> Option<PawnStructure> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return None;
+    }
+    let (mut white, mut black) = (Vec::new(), Vec::new());
+    for (i, rank_string) in ranks.iter().enumerate() {
+        let rank = 7 - (i as i8);
+        let mut file: i8 = 0;
+        for c in rank_string.chars() {
+            match c.to_digit(10) {
+                Some(skip) => {
+                    file += skip as i8;
+                }
+                None => {
+                    if c == 'P' {
+                        white.push((file, rank));
+                    } else if c == 'p' {
+                        black.push((file, rank));
+                    }
+                    file += 1;
+                }
+            }
+        }
+        if file != 8 {
+            return None;
+        }
+    }
+    white.sort();
+    black.sort();
+    return Some((white, black));
+}
+
+fn material(board: &board::Board, player: game::Player) -> Material {
+    let mut material = [0; 7];
+    for &(f, r) in board.get_pieces(player) {
+        material[board.squares[board::convert_position_1d(f, r)].get_type() as usize] += 1;
+    }
+    return material;
+}
+
+fn pawns(board: &board::Board, player: game::Player) -> Vec<(i8, i8)> {
+    let mut pawns: Vec<(i8, i8)> = board
+        .get_pieces(player)
+        .iter()
+        .copied()
+        .filter(|&(f, r)| {
+            board.squares[board::convert_position_1d(f, r)].get_type() == pieces::PieceType::Pawn
+        })
+        .collect();
+    pawns.sort();
+    return pawns;
+}
+
+impl PositionQuery {
+    pub fn matches(&self, board: &board::Board) -> bool {
+        if let Some(player) = self.side_to_move {
+            if pgn::side_to_move(board) != player {
+                return false;
+            }
+        }
+        for &(file, rank, piece) in &self.pieces {
+            let square = &board.squares[board::convert_position_1d(file, rank)];
+            let found = square.get_player().map(|p| (p, square.get_type()));
+            if found != piece {
+                return false;
+            }
+        }
+        if let Some((white, black)) = &self.material {
+            if
+                material(board, game::Player::White) != *white ||
+                material(board, game::Player::Black) != *black
+            {
+                return false;
+            }
+        }
+        if let Some((white, black)) = &self.pawns {
+            if pawns(board, game::Player::White) != *white || pawns(board, game::Player::Black) != *black {
+                return false;
+            }
+        }
+        if let Some(position) = &self.fen {
+            let wanted: Vec<&str> = position.split_whitespace().take(4).collect();
+            let current = fen::to_fen(board);
+            let current: Vec<&str> = current.split_whitespace().take(4).collect();
+            if wanted != current {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // can a game that has reached this board still reach the position?
+    // captured pieces never come back and pawns never move backwards or return once promoted
+    fn reachable(&self, board: &board::Board) -> bool {
+        let pawn = pieces::PieceType::Pawn as usize;
+        if let Some((white, black)) = &self.material {
+            let white_now = material(board, game::Player::White);
+            let black_now = material(board, game::Player::Black);
+            if
+                white_now[pawn] < white[pawn] ||
+                black_now[pawn] < black[pawn] ||
+                white_now.iter().sum::<u8>() < white.iter().sum::<u8>() ||
+                black_now.iter().sum::<u8>() < black.iter().sum::<u8>()
+            {
+                return false;
+            }
+        }
+        if let Some((white, black)) = &self.pawns {
+            if
+                pawns(board, game::Player::White).len() < white.len() ||
+                pawns(board, game::Player::Black).len() < black.len()
+            {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // replay a game from its starting position, returning the first ply that matches
+    // next_move gives the move to play from a position, or None once the game (or its valid part) ends
+    pub fn first_match<F>(&self, mut board: board::Board, mut next_move: F) -> Option<usize>
+        where F: FnMut(&board::Board) -> Option<moves::PieceMove>
+    {
+        let mut ply = 0;
+        loop {
+            if self.matches(&board) {
+                return Some(ply);
+            }
+            if !self.reachable(&board) {
+                return None;
+            }
+            let mv = next_move(&board)?;
+            if board.make_move(None, mv).is_err() {
+                return None;
+            }
+            ply += 1;
+        }
+    }
+}
+
+// search the main line of every game in a PGN source
+// games that can't be read are skipped; broken games are searched up to the first bad move
+pub fn search_pgn<R: BufRead>(query: &PositionQuery, reader: R) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    let mut games = pgn::PgnReader::new(reader);
+    while let Some(result) = games.next() {
+        let number = games.game_number();
+        let pgn_game = match result {
+            Ok(pgn_game) => pgn_game,
+            Err(_) => {
+                continue;
+            }
+        };
+        let board = match pgn::starting_board(pgn_game.get_tag("FEN")) {
+            Ok(board) => board,
+            Err(_) => {
+                continue;
+            }
+        };
+
+        let mut sans = pgn_game.moves.iter().map(|mv| mv.san.as_str());
+        let found = query.first_match(board, |board| {
+            let player = pgn::side_to_move(board);
+            return notation::parse_notation(board, &player, sans.next()?).ok();
+        });
+        if let Some(ply) = found {
+            hits.push(SearchHit {
+                game: number,
+                ply,
+                tags: pgn_game.tags.clone(),
+            });
+        }
+    }
+    return hits;
+}
+
+// search every game in a binary database
+pub fn search_database<R: Read + Seek>(
+    query: &PositionQuery,
+    database: &mut database::Database<R>
+) -> std::io::Result<Vec<SearchHit>> {
+    let mut hits = Vec::new();
+    for i in 0..database.len() {
+        let stored = database.get(i)?;
+        let board = match stored.starting_board() {
+            Ok(board) => board,
+            Err(_) => {
+                continue;
+            }
+        };
+        let mut codes = stored.moves.iter();
+        let found = query.first_match(board, |board| {
+            return Some(database::decode_move(board, *codes.next()?));
+        });
+        if let Some(ply) = found {
+            hits.push(SearchHit {
+                game: i + 1,
+                ply,
+                tags: stored.tags.clone(),
+            });
+        }
+    }
+    return Ok(hits);
+}
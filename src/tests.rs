@@ -5,3 +5,22 @@ pub mod knight_tests;
 pub mod bishop_tests;
 pub mod king_tests;
 pub mod game_tests;
+pub mod see_tests;
+pub mod fen_tests;
+pub mod validate_tests;
+pub mod board_tests;
+pub mod pgn_tests;
+pub mod pgn_validate_tests;
+pub mod normalize_tests;
+pub mod epd_tests;
+pub mod dataset_tests;
+pub mod database_tests;
+pub mod search_tests;
+pub mod movegen_tests;
+pub mod engine_tests;
+pub mod evaluation_tests;
+pub mod transposition_tests;
+pub mod time_tests;
+pub mod smp_tests;
+pub mod uci_tests;
+pub mod xboard_tests;
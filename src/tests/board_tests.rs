@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod board_tests {
+    use crate::board;
+    use crate::fen;
+    use crate::game;
+    use crate::pieces;
+
+    // kings are tracked no matter how they get onto (or off of) the board
+    #[test]
+    fn king_tracking() {
+        let mut board: board::Board = board::Board::new();
+        assert_eq!(board.get_king(game::Player::White), None);
+
+        board.place_piece(game::Player::White, pieces::PieceType::King, 6, 0);
+        board.place_piece(game::Player::Black, pieces::PieceType::King, 2, 7);
+        assert_eq!(board.get_king(game::Player::White), Some((6, 0)));
+        assert_eq!(board.get_king(game::Player::Black), Some((2, 7)));
+
+        // overwriting the king with another piece removes it
+        board.place_piece(game::Player::White, pieces::PieceType::Rook, 6, 0);
+        assert_eq!(board.get_king(game::Player::White), None);
+
+        board.place_piece(game::Player::White, pieces::PieceType::King, 4, 0);
+        board.clear_square(2, 7);
+        assert_eq!(board.get_king(game::Player::White), Some((4, 0)));
+        assert_eq!(board.get_king(game::Player::Black), None);
+    }
+
+    #[test]
+    fn piece_lists() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        assert_eq!(board.get_pieces(game::Player::White).len(), 16);
+        assert_eq!(board.get_pieces(game::Player::Black).len(), 16);
+
+        board.execute_notation(None, "e4").unwrap();
+        board.execute_notation(None, "d5").unwrap();
+        board.execute_notation(None, "exd5").unwrap();
+        assert_eq!(board.get_pieces(game::Player::White).len(), 16);
+        assert_eq!(board.get_pieces(game::Player::Black).len(), 15);
+        assert_eq!(board.get_pieces(game::Player::White).contains(&(3, 4)), true);
+        assert_eq!(board.get_pieces(game::Player::White).contains(&(4, 3)), false);
+
+        // castling moves the king and rook
+        board.execute_notation(None, "Nf6").unwrap();
+        board.execute_notation(None, "Nf3").unwrap();
+        board.execute_notation(None, "a6").unwrap();
+        board.execute_notation(None, "Be2").unwrap();
+        board.execute_notation(None, "a5").unwrap();
+        board.execute_notation(None, "O-O").unwrap();
+        assert_eq!(board.get_king(game::Player::White), Some((6, 0)));
+        assert_eq!(board.get_pieces(game::Player::White).contains(&(5, 0)), true);
+        assert_eq!(board.get_pieces(game::Player::White).contains(&(7, 0)), false);
+    }
+
+    // a rejected move leaves the caches untouched
+    #[test]
+    fn king_tracking_illegal_move() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::King, 4, 0);
+        board.place_piece(game::Player::Black, pieces::PieceType::King, 4, 7);
+        board.place_piece(game::Player::Black, pieces::PieceType::Rook, 3, 7);
+
+        assert_eq!(board.execute_notation(None, "Kd1").is_err(), true);
+        assert_eq!(board.get_king(game::Player::White), Some((4, 0)));
+        board.execute_notation(None, "Kf1").unwrap();
+        assert_eq!(board.get_king(game::Player::White), Some((5, 0)));
+        assert_eq!(board.get_pieces(game::Player::White), &vec![(5, 0)]);
+    }
+
+    // check is detected against the real king square on hand built boards
+    #[test]
+    fn hand_built_check() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::King, 7, 0);
+        board.place_piece(game::Player::Black, pieces::PieceType::King, 7, 7);
+        board.place_piece(game::Player::Black, pieces::PieceType::Rook, 6, 7);
+        assert_eq!(board.is_in_check(game::Player::White), false);
+
+        board.place_piece(game::Player::Black, pieces::PieceType::Rook, 7, 4);
+        assert_eq!(board.is_in_check(game::Player::White), true);
+        assert_eq!(board.execute_notation(None, "Kg1").is_err(), true);
+        assert_eq!(board.execute_notation(None, "Kg2").is_err(), true);
+    }
+
+    // clones keep each piece's move history (castling rights, en passant)
+    #[test]
+    fn clone_keeps_history() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        board.execute_notation(None, "Nf3").unwrap();
+        board.execute_notation(None, "Nf6").unwrap();
+        board.execute_notation(None, "Rg1").unwrap();
+
+        let clone = board.clone();
+        let rook = &clone.squares[board::convert_position_1d(6, 0)];
+        assert_eq!(rook.get_last_move().is_some(), true);
+        assert_eq!(clone.get_pieces(game::Player::White), board.get_pieces(game::Player::White));
+    }
+
+    // a pawn can only capture diagonally, so it can't block a check by stepping onto an empty square
+    #[test]
+    fn mate_with_only_pawn_captures_in_the_way() {
+        let mut board = fen::parse_fen("k7/3b4/8/8/2P5/8/7P/6BK b - - 0 1").unwrap();
+        board.execute_notation(None, "Bc6+").unwrap();
+        assert_eq!(board.get_state(), &game::GameState::Won(game::Player::Black));
+
+        // a pawn that can push onto the line still blocks
+        let mut board = fen::parse_fen("k7/3b4/8/8/8/8/4P2P/6BK b - - 0 1").unwrap();
+        board.execute_notation(None, "Bc6+").unwrap();
+        assert_eq!(board.get_state(), &game::GameState::Playing(game::Player::White));
+    }
+}
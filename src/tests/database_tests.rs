@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod database_tests {
+    use crate::database;
+    use crate::fen;
+    use crate::moves;
+    use crate::pgn;
+    use crate::pieces;
+
+    // moves fit in 16 bits, promotions included
+    #[test]
+    fn move_encoding() {
+        let board = fen::parse_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        for mv in [
+            moves::PieceMove::new(pieces::PieceType::Knight, 1, 6, 1, 7),
+            moves::PieceMove::new(pieces::PieceType::Queen, 1, 6, 1, 7),
+            moves::PieceMove::new(pieces::PieceType::King, 4, 0, 6, 0),
+            moves::PieceMove::new(pieces::PieceType::Rook, 7, 0, 7, 7),
+        ] {
+            let code = database::encode_move(&board, mv);
+            assert_eq!(database::decode_move(&board, code), mv);
+        }
+        let castle = moves::PieceMove::new(pieces::PieceType::King, 4, 0, 6, 0);
+        assert_eq!(database::encode_move(&board, castle), 4 | (6 << 6));
+    }
+
+    // games can be converted to the database, read back in any order, and converted back to PGN
+    #[test]
+    fn database_round_trip() {
+        let mut text = String::new();
+        for i in 1..=5 {
+            text += &std::fs::read_to_string(format!("./games/{i}.pgn")).unwrap();
+            text += "\n\n";
+        }
+        text += "[Event \"broken\"]\n\n1. e4 e4 *\n";
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let reports = database::pgn_to_database(std::io::Cursor::new(&text), &mut bytes).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].game, 6);
+        assert_eq!(bytes.len() < text.len() / 2, true);
+
+        let originals: Vec<pgn::PgnGame> = pgn::PgnReader::new(std::io::Cursor::new(&text))
+            .take(5)
+            .map(|game| game.unwrap())
+            .collect();
+        let mut db = database::Database::open(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(db.len(), 5);
+        for i in [4, 0, 2] {
+            let stored = db.get(i).unwrap();
+            assert_eq!(stored.tags, originals[i].tags);
+            assert_eq!(stored.moves.len(), originals[i].moves.len());
+            let decoded = database::decode_game(&stored).unwrap();
+            assert_eq!(decoded.main_line(), originals[i].main_line());
+            assert_eq!(decoded.result, originals[i].result);
+        }
+        assert_eq!(db.get(5).is_err(), true);
+
+        let mut output: Vec<u8> = Vec::new();
+        let reports = database::database_to_pgn(&mut db, &mut output).unwrap();
+        assert_eq!(reports.len(), 0);
+        let games = pgn::parse_pgn_games(&String::from_utf8(output).unwrap()).unwrap();
+        assert_eq!(games.len(), 5);
+    }
+
+    #[test]
+    fn custom_start() {
+        let game = pgn::parse_pgn(
+            "[FEN \"4k3/1P6/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. O-O Kd7 2. b8=N+ *"
+        ).unwrap();
+        let stored = database::encode_game(&game).unwrap();
+        assert_eq!(database::decode_game(&stored).unwrap().main_line(), vec!["O-O", "Kd7", "b8=N+"]);
+    }
+
+    #[test]
+    fn invalid_database() {
+        let bytes = b"PGN!\x01".to_vec();
+        assert_eq!(database::Database::open(std::io::Cursor::new(bytes)).is_err(), true);
+
+        let writer = database::DatabaseWriter::new(Vec::new()).unwrap();
+        let bytes = writer.finish().unwrap();
+        let db = database::Database::open(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(db.is_empty(), true);
+    }
+}
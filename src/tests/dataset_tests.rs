@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod dataset_tests {
+    use crate::dataset;
+    use crate::game;
+    use crate::moves;
+    use crate::pgn;
+    use crate::validate;
+
+    #[test]
+    fn rows_per_ply() {
+        let game = pgn::parse_pgn(
+            "1. e4 {[%clk 0:05:00] [%eval 0.3]} e5 {[%eval #-2]} 2. Ngf3 {[%clk 0:04:58]} 1-0"
+        ).unwrap();
+        let rows = dataset::dataset_rows(&game, 7).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], dataset::DatasetRow {
+            game: 7,
+            ply: 1,
+            fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+            san: "e4".to_string(),
+            uci: "e2e4".to_string(),
+            side: 'w',
+            result: "1-0".to_string(),
+            clock: Some("0:05:00".to_string()),
+            eval: Some("0.3".to_string()),
+        });
+        assert_eq!(rows[1].side, 'b');
+        assert_eq!(rows[1].clock, None);
+        assert_eq!(rows[1].eval, Some("#-2".to_string()));
+        assert_eq!(rows[2].san, "Nf3");
+        assert_eq!(rows[2].uci, "g1f3");
+    }
+
+    // promotions carry the piece in UCI and castling is the king's move
+    #[test]
+    fn uci_moves() {
+        let game = pgn::parse_pgn(
+            "[FEN \"4k3/1P6/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. O-O Kd7 2. b8=N+ *"
+        ).unwrap();
+        let rows = dataset::dataset_rows(&game, 1).unwrap();
+        let uci: Vec<&str> = rows
+            .iter()
+            .map(|row| row.uci.as_str())
+            .collect();
+        assert_eq!(uci, vec!["e1g1", "e8d7", "b7b8n"]);
+        assert_eq!(rows[2].san, "b8=N+");
+    }
+
+    #[test]
+    fn export_formats() {
+        let text = concat!(
+            "[Event \"a\"]\n\n1. e4 {[%clk 0:05:00]} *\n\n",
+            "[Event \"b\"]\n\n1. e5 *\n\n",
+            "[Event \"c\"]\n\n1. d4 1/2-1/2\n"
+        );
+
+        let mut output: Vec<u8> = Vec::new();
+        let reports = dataset::export_dataset(
+            std::io::Cursor::new(text),
+            &mut output,
+            dataset::DatasetFormat::Csv
+        ).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].game, 2);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "game,ply,fen,san,uci,side,result,clock,eval\n",
+                "1,1,rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1,e4,e2e4,w,*,0:05:00,\n",
+                "3,1,rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1,d4,d2d4,w,1/2-1/2,,\n"
+            )
+        );
+
+        let mut output: Vec<u8> = Vec::new();
+        dataset::export_dataset(
+            std::io::Cursor::new(text),
+            &mut output,
+            dataset::DatasetFormat::JsonLines
+        ).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert_eq!(
+            output.lines().next().unwrap(),
+            concat!(
+                "{\"game\":1,\"ply\":1,\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",",
+                "\"san\":\"e4\",\"uci\":\"e2e4\",\"side\":\"w\",\"result\":\"*\",\"clock\":\"0:05:00\",\"eval\":null}"
+            )
+        );
+    }
+
+    // a game with an ambiguous move is reported and skipped, leaving nothing but rows in the output
+    #[test]
+    fn ambiguous_move() {
+        let text = "1. d4 d5 2. Nf3 Nf6 3. Nd2 *\n";
+        let mut output: Vec<u8> = Vec::new();
+        let reports = dataset::export_dataset(
+            std::io::Cursor::new(text),
+            &mut output,
+            dataset::DatasetFormat::JsonLines
+        ).unwrap();
+        assert_eq!(output.is_empty(), true);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].issue,
+            validate::ValidationIssue::IllegalMove {
+                move_number: 3,
+                player: game::Player::White,
+                san: "Nd2".to_string(),
+                error: moves::MoveError::AmbiguousMove,
+            }
+        );
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod engine_tests {
+    use crate::engine;
+    use crate::evaluation;
+    use crate::fen;
+    use crate::game;
+    use crate::moves;
+    use crate::notation;
+    use crate::pieces;
+
+    fn search(position: &str, depth: u32) -> engine::SearchResult {
+        let board = fen::parse_fen(position).unwrap();
+        return engine::Engine::new().search(&board, &engine::SearchLimits { depth, ..Default::default() });
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(result.best_move, Some(moves::PieceMove::new(pieces::PieceType::Rook, 0, 0, 0, 7)));
+        assert_eq!(result.score, engine::Score::Mate(1));
+        assert_eq!(result.score.to_string(), "#1");
+        assert_eq!(result.pv.len(), 1);
+    }
+
+    // the side getting mated sees a negative mate score
+    #[test]
+    fn mate_in_two() {
+        let result = search("3r2k1/5ppp/8/8/8/8/4R3/4R1K1 w - - 0 1", 4);
+        assert_eq!(result.score, engine::Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+
+        let result = search("7k/8/6KQ/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.score, engine::Score::Mate(-1));
+    }
+
+    #[test]
+    fn wins_hanging_piece() {
+        let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 3);
+        assert_eq!(result.best_move, Some(moves::PieceMove::new(pieces::PieceType::Rook, 3, 1, 3, 4)));
+        assert_eq!(matches!(result.score, engine::Score::Centipawns(cp) if cp > 300), true);
+        assert_eq!(result.score.to_string().starts_with('+'), true);
+    }
+
+    // positions with no legal moves have no best move
+    #[test]
+    fn no_legal_moves() {
+        let result = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, engine::Score::Centipawns(0));
+
+        let result = search("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, engine::Score::Mate(0));
+    }
+
+    // every move of the principal variation can be played in order
+    #[test]
+    fn principal_variation_is_legal() {
+        let mut board = fen::parse_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+        ).unwrap();
+        let result = engine::Engine::new().search(&board, &engine::SearchLimits { depth: 3, ..Default::default() });
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.pv.len(), 3);
+        assert_eq!(result.best_move, Some(result.pv[0]));
+        for mv in result.pv {
+            let san = notation::to_san(&board, mv);
+            let player = if board.get_turn() % 2 == 0 { game::Player::White } else { game::Player::Black };
+            board.execute_notation(Some(player), &san).unwrap();
+        }
+    }
+
+    // a pawn defended by a pawn isn't free, even on the last ply of the search
+    #[test]
+    fn quiescence_sees_recaptures() {
+        let result = search("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert_eq!(result.best_move == Some(moves::PieceMove::new(pieces::PieceType::Queen, 3, 0, 3, 4)), false);
+
+        // while an undefended pawn is simply won
+        let position = "4k3/8/2n5/3p4/4P3/8/8/4K3 w - - 0 1";
+        let result = search(position, 1);
+        assert_eq!(result.best_move, Some(moves::PieceMove::new(pieces::PieceType::Pawn, 4, 3, 3, 4)));
+        let before = evaluation::evaluate(&fen::parse_fen(position).unwrap(), game::Player::White);
+        assert_eq!(matches!(result.score, engine::Score::Centipawns(cp) if cp > before + 50), true);
+    }
+
+    // checks on the first quiescence ply let a shallow search see a forced mate
+    #[test]
+    fn quiescence_checks() {
+        let board = fen::parse_fen("3r2k1/5ppp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap();
+        let mut first = None;
+        engine::Engine::new().search_with(&board, &engine::SearchLimits { depth: 1, ..Default::default() }, |result| {
+            first = Some(result.score);
+        });
+        assert_eq!(first, Some(engine::Score::Mate(2)));
+    }
+
+    // the hash move comes first, then the most valuable victims taken by the least valuable attackers
+    #[test]
+    fn move_ordering() {
+        let board = fen::parse_fen("4k3/8/8/3q1p2/2P5/8/8/3RKR2 w - - 0 1").unwrap();
+        let mut moves = board.legal_moves();
+        let hash_move = moves::PieceMove::new(pieces::PieceType::King, 4, 0, 4, 1);
+        engine::Engine::new().order_moves(&board, &mut moves, Some(hash_move), 0);
+
+        assert_eq!(moves[0], hash_move);
+        assert_eq!(moves[1], moves::PieceMove::new(pieces::PieceType::Pawn, 2, 3, 3, 4));
+        assert_eq!(moves[2], moves::PieceMove::new(pieces::PieceType::Rook, 3, 0, 3, 4));
+        assert_eq!(moves[3], moves::PieceMove::new(pieces::PieceType::Rook, 5, 0, 5, 4));
+        assert_eq!(moves[4..].iter().any(|mv| board.is_capture(*mv)), false);
+    }
+
+    // quiet moves that refuted a line are tried early when the search comes back to it
+    #[test]
+    fn killers_and_history() {
+        let board = fen::parse_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut engine = engine::Engine::new();
+        engine.search(&board, &engine::SearchLimits { depth: 4, ..Default::default() });
+
+        // a fresh engine can only tell captures apart, while this one has learned about quiet moves
+        let mut fresh = board.legal_moves();
+        engine::Engine::new().order_moves(&board, &mut fresh, None, 0);
+        let mut learned = board.legal_moves();
+        engine.order_moves(&board, &mut learned, None, 0);
+        assert_eq!(learned == fresh, false);
+        assert_eq!(learned[0], fresh[0]);
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod epd_tests {
+    use crate::epd;
+    use crate::fen;
+    use crate::moves;
+    use crate::pieces;
+
+    #[test]
+    fn parse_operations() {
+        let line = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+        let position = epd::parse_epd(line).unwrap();
+        assert_eq!(position.get_operation("bm"), Some(&vec!["Qg6".to_string()]));
+        assert_eq!(position.get_operation("id"), Some(&vec!["WAC.001".to_string()]));
+        assert_eq!(position.get_operation("am"), None);
+        assert_eq!(
+            fen::to_fen(&position.board),
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"
+        );
+        assert_eq!(
+            position.get_moves("bm").unwrap(),
+            vec![moves::PieceMove::new(pieces::PieceType::Queen, 6, 2, 6, 5)]
+        );
+        assert_eq!(epd::to_epd(&position), line);
+    }
+
+    // quoted operands may hold spaces and semicolons, and the move counters come from hmvc and fmvn
+    #[test]
+    fn quoted_operands() {
+        let line = concat!(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - ",
+            "bm Bb5 Bc4; c0 \"Spanish; or Italian\"; ce 25; pv Bb5 a6; hmvc 2; fmvn 3"
+        );
+        let position = epd::parse_epd(line).unwrap();
+        assert_eq!(position.get_operation("c0"), Some(&vec!["Spanish; or Italian".to_string()]));
+        assert_eq!(position.get_operation("pv").unwrap().len(), 2);
+        assert_eq!(position.get_moves("bm").unwrap().len(), 2);
+        assert_eq!(position.board.halfmove_clock, 2);
+        assert_eq!(position.board.get_turn(), 4);
+        assert_eq!(
+            epd::to_epd(&position),
+            concat!(
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - ",
+                "bm Bb5 Bc4; c0 \"Spanish; or Italian\"; ce 25; pv Bb5 a6; hmvc 2; fmvn 3;"
+            )
+        );
+    }
+
+    #[test]
+    fn write_operations() {
+        let mut position = epd::Epd::new(fen::parse_fen(fen::STARTING_FEN).unwrap());
+        position.set_operation("id", vec!["start".to_string()]);
+        position.set_operation("bm", vec!["e4".to_string()]);
+        position.set_operation("id", vec!["opening position".to_string()]);
+        assert_eq!(
+            epd::to_epd(&position),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"opening position\"; bm e4;"
+        );
+    }
+
+    #[test]
+    fn invalid_epd() {
+        let position = "4k3/8/8/8/8/8/8/4K3 w - -";
+        assert_eq!(epd::parse_epd(position).is_ok(), true);
+        for line in [
+            "4k3/8/8/8/8/8/8/4K3 w -",
+            "4k3/8/8/8/8/8/8/4K3 w - - id \"unterminated;",
+            "4k3/8/8/8/8/8/8/4K3 w - - ;",
+            "4k3/8/8/8/8/8/8/4K3 w - - 1x foo;",
+            "4k3/8/8/8/8/8/8/4K3 w - - hmvc 1 2;",
+        ] {
+            assert_eq!(epd::parse_epd(line).is_err(), true);
+        }
+        assert_eq!(
+            epd::parse_epd("4k3/8/8/8/8/8/8/4K3 w - - bm Ke5").unwrap().get_moves("bm").is_err(),
+            true
+        );
+        assert_eq!(epd::parse_epd("4k3/8/8/8/8/8/8/4K3 w - - id;").is_ok(), true);
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod evaluation_tests {
+    use crate::evaluation;
+    use crate::fen;
+    use crate::game;
+
+    fn evaluate(position: &str) -> evaluation::Evaluation {
+        return evaluation::evaluate_position(&fen::parse_fen(position).unwrap());
+    }
+
+    // mirrored positions score the same for the other side
+    #[test]
+    fn symmetric() {
+        let start = evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(start.total(), 0);
+        assert_eq!(start.phase, 24);
+
+        let white = evaluate("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let black = evaluate("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR w KQkq - 2 3");
+        assert_eq!(white.total(), -black.total());
+        assert_eq!(white.score(game::Player::White), white.total());
+        assert_eq!(white.score(game::Player::Black), -white.total());
+    }
+
+    #[test]
+    fn term_breakdown() {
+        // a knight for a pawn and no bishops on the board
+        let evaluation = evaluate("4k3/pp6/8/8/8/8/P7/1N2K3 w - - 0 1");
+        assert_eq!(evaluation.material.total(), 220);
+        assert_eq!(evaluation.bishop_pair.total(), 0);
+        assert_eq!(evaluation.phase, 1);
+        let sum: i32 = evaluation
+            .terms()
+            .iter()
+            .map(|(_, term)| term.total())
+            .sum();
+        assert_eq!(evaluation.total(), sum);
+
+        let text = evaluation.to_string();
+        assert_eq!(text.lines().count(), 9);
+        assert_eq!(text.contains("Material"), true);
+        assert_eq!(text.contains("+2.20"), true);
+    }
+
+    // the phase decides between middlegame and endgame piece-square tables
+    #[test]
+    fn king_placement() {
+        let endgame = evaluate("8/8/8/3k4/8/8/8/K7 w - - 0 1");
+        assert_eq!(endgame.phase, 0);
+        assert_eq!(endgame.piece_squares.total() < 0, true);
+        assert_eq!(endgame.king_safety.total(), 0);
+
+        // a castled king is safer than one stuck in the centre
+        let middlegame = evaluate("r1bq1rk1/ppppbppp/2n2n2/4p3/4P3/2N2N2/PPPPKPPP/R1BQ1B1R w - - 0 1");
+        assert_eq!(middlegame.king_safety.total() < 0, true);
+        assert_eq!(middlegame.piece_squares.total() < 0, true);
+    }
+
+    #[test]
+    fn pawn_structure() {
+        // doubled and isolated pawns against a healthy pair
+        let weak = evaluate("4k3/5pp1/8/8/8/2P5/2P5/4K3 w - - 0 1");
+        assert_eq!(weak.pawn_structure.white < weak.pawn_structure.black, true);
+
+        // an advanced passed pawn is worth more than one at home
+        let advanced = evaluate("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let home = evaluate("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+        assert_eq!(advanced.pawn_structure.white > home.pawn_structure.white, true);
+    }
+
+    #[test]
+    fn bishop_pair_and_mobility() {
+        let evaluation = evaluate("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1");
+        assert_eq!(evaluation.bishop_pair.total() > 0, true);
+
+        // a rook on an open board moves further than one boxed in by its own pawns
+        let open = evaluate("4k3/8/8/8/3R4/8/8/N3K3 w - - 0 1");
+        let closed = evaluate("4k3/8/8/8/8/P7/RP6/N3K3 w - - 0 1");
+        assert_eq!(open.mobility.white > closed.mobility.white, true);
+    }
+}
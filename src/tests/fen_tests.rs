@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod fen_tests {
+    use crate::board;
+    use crate::fen;
+    use crate::game;
+    use crate::pieces;
+
+    #[test]
+    fn starting_position_fen() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        assert_eq!(fen::to_fen(&board), fen::STARTING_FEN);
+
+        let board = fen::parse_fen(fen::STARTING_FEN).unwrap();
+        assert_eq!(fen::to_fen(&board), fen::STARTING_FEN);
+        assert_eq!(board.get_state(), &game::GameState::Playing(game::Player::White));
+    }
+
+    // the FEN of a played position matches the well known values
+    #[test]
+    fn played_position_fen() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        board.execute_notation(None, "e4").unwrap();
+        assert_eq!(
+            fen::to_fen(&board),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+        board.execute_notation(None, "c5").unwrap();
+        board.execute_notation(None, "Nf3").unwrap();
+        assert_eq!(
+            fen::to_fen(&board),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let positions = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 3 20",
+        ];
+        for position in positions {
+            let board = fen::parse_fen(position).unwrap();
+            assert_eq!(fen::to_fen(&board), position);
+        }
+    }
+
+    // castling rights and en passant from FEN are honoured when playing on
+    #[test]
+    fn fen_special_moves() {
+        let mut board = fen::parse_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        assert_eq!(board.execute_notation(None, "O-O-O").is_err(), true);
+        board.execute_notation(None, "O-O").unwrap();
+        board.execute_notation(None, "O-O-O").unwrap();
+        assert_eq!(
+            board.squares[board::convert_position_1d(2, 7)].get_type(),
+            pieces::PieceType::King
+        );
+
+        let mut board = fen::parse_fen(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+        ).unwrap();
+        assert_eq!(board.execute_notation(None, "exd6").is_err(), true);
+        board.execute_notation(None, "exf6").unwrap();
+    }
+
+    #[test]
+    fn invalid_fen() {
+        let invalid = [
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a 1",
+            // move counters too large to keep counting
+            "k7/8/8/8/8/8/8/7K w - - 0 2000000000",
+            "k7/8/8/8/8/8/8/7K b - - 0 1073741824",
+            "k7/8/8/8/8/8/8/7K w - - 2147483647 1",
+        ];
+        for position in invalid {
+            assert_eq!(fen::parse_fen(position).err(), Some(board::SetupError::InvalidFen));
+        }
+
+        // an en passant square off the third or sixth rank is rejected, even on the edge of the board
+        let invalid_en_passant = [
+            "4k3/8/8/8/8/8/8/4K3 w - e1 0 1",
+            "4k3/8/8/8/8/8/8/4K3 b - e8 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+        ];
+        for position in invalid_en_passant {
+            assert_eq!(fen::parse_fen(position).err(), Some(board::SetupError::InvalidEnPassant));
+        }
+    }
+}
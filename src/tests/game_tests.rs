@@ -1,40 +1,27 @@
 #[cfg(test)]
 mod game_tests {
     use crate::board;
+    use crate::fen;
     use crate::game;
+    use crate::moves;
+    use crate::pgn;
+    use crate::pieces;
+    use crate::validate;
 
     fn import_game_from_file(file_path: &str) -> (Vec<String>, String) {
         let contents = std::fs
             ::read_to_string(file_path)
             .expect("Should have been able to read the file");
+        let game = pgn::parse_pgn(&contents).unwrap();
 
-        // extract result from header
-        let result_regex = regex::Regex::new(r#"\[Result\s*"([01/2-]+)"\]"#).unwrap();
-        let result = result_regex
-            .captures(&contents)
-            .map(|caps| caps.get(1).map(|m| m.as_str().to_string()))
-            .unwrap();
-
-        // strip header (lines wrapped in [])
-        let header_regex = regex::Regex::new(r"(?m)^\[.*\]\s*").unwrap();
-        let stripped_contents = header_regex.replace_all(&contents, "");
-
-        // remove move numbers from notation
-        let move_numbers_regex = regex::Regex::new(r"\d+\.\s*").unwrap();
-        let stripped_contents = move_numbers_regex.replace_all(&stripped_contents, "");
-
-        let move_regex = regex::Regex
-            ::new(
-                r"(1-0|0-1|1/2-1/2)|([KQRBN]?[a-h]?[1-8]?x?[a-h]?[1-8](=[QRBN])?[+#]?|O-O(?:-O)?)"
-            )
-            .unwrap();
-
-        let move_notations: Vec<String> = move_regex
-            .find_iter(&stripped_contents)
-            .map(|mat| mat.as_str().to_string())
+        let mut move_notations: Vec<String> = game
+            .main_line()
+            .iter()
+            .map(|san| san.to_string())
             .collect();
+        move_notations.push(game.result.clone());
 
-        return (move_notations, result.unwrap());
+        return (move_notations, game.get_tag("Result").unwrap().to_string());
     }
 
     fn result_to_state(result: &str) -> game::GameState {
@@ -45,19 +32,140 @@ mod game_tests {
         }
     }
 
+    // replay every sample game; failures name the game, move and error
     #[test]
     fn simulate_all_games() {
         let paths = std::fs::read_dir("./games").unwrap();
         for path in paths {
+            let path = path.unwrap().path();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let game = pgn::parse_pgn(&contents).unwrap();
+            let issues: Vec<String> = validate::validate_game(&game)
+                .iter()
+                .map(|issue| format!("{}: {issue}", path.display()))
+                .collect();
+            assert_eq!(issues, Vec::<String>::new());
+
             let mut board: board::Board = board::Board::new();
             board.reset_board();
-            let game = import_game_from_file(path.unwrap().path().to_str().unwrap());
-            for mv in game.0 {
-                let res = board.execute_notation(None, &mv);
-                assert_eq!(res.is_ok(), true);
+            for mv in import_game_from_file(path.to_str().unwrap()).0 {
+                board.execute_notation(None, &mv).unwrap();
             }
+            assert_eq!(board.get_state(), &result_to_state(game.get_tag("Result").unwrap()));
+        }
+    }
+
+    // a game keeps every move in standard notation along with the positions reached
+    #[test]
+    fn game_history() {
+        let mut game = game::Game::new();
+        game.play_notation("e4").unwrap();
+        game.play_notation("e5").unwrap();
+        game.play_notation("Ngf3").unwrap();
+        game.set_clock(std::time::Duration::from_secs(295));
+
+        let moves = game.get_moves();
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[2].san, "Nf3");
+        assert_eq!(moves[2].mv, moves::PieceMove::new(pieces::PieceType::Knight, 6, 0, 5, 2));
+        assert_eq!(moves[2].clock, Some(std::time::Duration::from_secs(295)));
+        assert_eq!(moves[1].clock, None);
+
+        let positions = game.get_positions();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[0], "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(positions[3], "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
 
-            assert_eq!(board.get_state(), &result_to_state(&game.1));
+        // illegal moves are not recorded
+        assert_eq!(game.play_notation("Ke3").is_err(), true);
+        assert_eq!(game.get_moves().len(), 3);
+    }
+
+    #[test]
+    fn game_termination() {
+        let mut game = game::Game::new();
+        for mv in ["f3", "e5", "g4", "Qh4"] {
+            game.play_notation(mv).unwrap();
         }
+        assert_eq!(game.get_moves()[3].san, "Qh4#");
+        assert_eq!(game.get_result(), &game::GameState::Won(game::Player::Black));
+        assert_eq!(game.termination, game::Termination::Checkmate);
+        assert_eq!(game.play_notation("a3").is_err(), true);
+
+        let mut game = game::Game::new();
+        game.play_notation("e4").unwrap();
+        game.play_notation("0-1").unwrap();
+        assert_eq!(game.get_result(), &game::GameState::Won(game::Player::Black));
+        assert_eq!(game.termination, game::Termination::Resignation);
+        assert_eq!(game.get_moves().len(), 1);
+
+        let mut game = game::Game::from_board(fen::parse_fen("k7/2Q5/8/8/8/8/8/7K w - - 0 1").unwrap());
+        game.play_notation("Qb6").unwrap();
+        assert_eq!(game.get_result(), &game::GameState::Draw);
+        assert_eq!(game.termination, game::Termination::Stalemate);
+
+        // a mate in a side variation doesn't end the game
+        let mut game = game::Game::new();
+        for mv in ["f3", "e5", "g4", "Nc6"] {
+            game.play_notation(mv).unwrap();
+        }
+        game.back();
+        game.play_notation("Qh4").unwrap();
+        assert_eq!(game.is_main_line(), false);
+        assert_eq!(game.termination, game::Termination::Unterminated);
+    }
+
+    // moves played after going back start variations, which can be explored and rearranged
+    #[test]
+    fn game_tree() {
+        let mut game = game::Game::new();
+        for mv in ["e4", "e5", "Nf3"] {
+            game.play_notation(mv).unwrap();
+        }
+        assert_eq!(game.back(), true);
+        assert_eq!(game.back(), true);
+        game.play_notation("c5").unwrap();
+        game.play_notation("Nf3").unwrap();
+        let sicilian = game.get_current();
+
+        // the main line is unchanged and the variation is where we are
+        let main_line: Vec<&str> = game
+            .get_main_line()
+            .iter()
+            .map(|mv| mv.san.as_str())
+            .collect();
+        assert_eq!(main_line, vec!["e4", "e5", "Nf3"]);
+        let path: Vec<&str> = game
+            .get_moves()
+            .iter()
+            .map(|mv| mv.san.as_str())
+            .collect();
+        assert_eq!(path, vec!["e4", "c5", "Nf3"]);
+
+        // returning goes back to where the variation branched off
+        assert_eq!(game.exit_variation(), true);
+        assert_eq!(game.get_moves().len(), 1);
+        assert_eq!(game.exit_variation(), false);
+        assert_eq!(game.forward(), true);
+        assert_eq!(game.get_moves()[1].san, "e5");
+        assert_eq!(game.back(), true);
+        assert_eq!(game.enter_variation(1), true);
+        assert_eq!(game.get_moves()[1].san, "c5");
+        assert_eq!(game.enter_variation(1), false);
+
+        // playing a move that is already in the tree follows it
+        assert_eq!(game.back(), true);
+        game.play_notation("c5").unwrap();
+        game.play_notation("Nf3").unwrap();
+        assert_eq!(game.get_current(), sicilian);
+
+        assert_eq!(game.promote_variation(), true);
+        assert_eq!(game.get_main_line()[1].san, "c5");
+        assert_eq!(game.promote_variation(), false);
+
+        assert_eq!(game.delete_branch(), true);
+        assert_eq!(game.get_main_line().len(), 2);
+        assert_eq!(game.get_moves().len(), 2);
+        assert_eq!(game.get_board().get_turn(), 2);
     }
 }
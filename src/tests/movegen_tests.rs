@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod movegen_tests {
+    use crate::board;
+    use crate::fen;
+    use crate::game;
+
+    // count the leaf nodes of the legal move tree
+    fn perft(board: &board::Board, depth: u32) -> u64 {
+        let moves = board.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for mv in moves {
+            let mut child = board.clone();
+            child.make_move(None, mv).unwrap();
+            nodes += perft(&child, depth - 1);
+        }
+        return nodes;
+    }
+
+    // reference counts from the Chess Programming Wiki
+    #[test]
+    fn perft_positions() {
+        for (position, counts) in [
+            (fen::STARTING_FEN, vec![20, 400, 8902]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec![48, 2039]),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", vec![14, 191, 2812]),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", vec![6, 264]),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", vec![44, 1486]),
+        ] {
+            let board = fen::parse_fen(position).unwrap();
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!((position, depth + 1, perft(&board, (depth + 1) as u32)), (position, depth + 1, *count));
+            }
+        }
+    }
+
+    // a player with no legal moves who isn't in check is stalemated
+    #[test]
+    fn stalemate() {
+        let mut board = fen::parse_fen("7k/8/6Q1/8/8/8/8/K7 w - - 0 1").unwrap();
+        board.execute_notation(None, "Qf7").unwrap();
+        assert_eq!(board.get_state(), &game::GameState::Draw);
+        assert_eq!(board.legal_moves().len(), 0);
+    }
+
+    // the king may not castle out of, through or into check
+    #[test]
+    fn castling_through_check() {
+        for (position, castles) in [
+            ("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", 2),
+            ("4k3/8/8/8/8/8/6r1/R3K2R w KQ - 0 1", 1),
+            ("4k3/8/8/8/8/5r2/8/R3K2R w KQ - 0 1", 1),
+            ("4k3/8/8/8/8/6r1/8/R3K2R w KQ - 0 1", 1),
+            ("4k3/8/8/8/8/4r3/8/R3K2R w KQ - 0 1", 0),
+            ("4k3/8/8/8/8/1r6/8/R3K2R w KQ - 0 1", 2),
+        ] {
+            let board = fen::parse_fen(position).unwrap();
+            let count = board
+                .legal_moves()
+                .iter()
+                .filter(|mv| board::is_castle(mv.piece_type, **mv))
+                .count();
+            assert_eq!((position, count), (position, castles));
+        }
+    }
+
+    // pawns only move two squares from their starting rank, and not through pieces
+    #[test]
+    fn pawn_double_step() {
+        let mut board = fen::parse_fen("4k3/8/8/8/8/4n3/P3P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.execute_notation(None, "e4").is_err(), true);
+        let mut moved = fen::parse_fen("4k3/8/8/8/8/P7/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(moved.execute_notation(None, "a5").is_err(), true);
+        assert_eq!(board.execute_notation(None, "a4").is_ok(), true);
+    }
+}
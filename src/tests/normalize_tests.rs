@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod normalize_tests {
+    use crate::normalize;
+    use crate::pgn;
+    use crate::validate;
+    use crate::game;
+    use crate::moves;
+
+    // SAN is rewritten from the moves: needless disambiguation goes and check marks are added
+    #[test]
+    fn regenerate_san() {
+        let game = pgn::parse_pgn(
+            "1. e4 e5 2. Ngf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. d4 Bg4 6. dxe5 Qxd1 7. Kxd1 *"
+        ).unwrap();
+        let normalized = normalize::normalize_game(&game, &normalize::NormalizeOptions::default());
+        assert_eq!(normalized.unwrap().main_line(), vec![
+            "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "d4", "Bg4", "dxe5", "Qxd1+", "Kxd1"
+        ]);
+    }
+
+    // the Seven Tag Roster comes first and is filled in; other tags follow alphabetically
+    #[test]
+    fn order_tags() {
+        let game = pgn::parse_pgn(
+            "[White \"A\"]\n[ECO \"C20\"]\n[Event \"x\"]\n[Annotator \"me\"]\n[Result \"1-0\"]\n\n1. e4 *"
+        ).unwrap();
+        let normalized = normalize::normalize_game(
+            &game,
+            &normalize::NormalizeOptions::default()
+        ).unwrap();
+        let names: Vec<&str> = normalized.tags
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec![
+            "Event", "Site", "Date", "Round", "White", "Black", "Result", "Annotator", "ECO"
+        ]);
+        assert_eq!(normalized.get_tag("Site"), Some("?"));
+        assert_eq!(normalized.get_tag("Result"), Some("*"));
+    }
+
+    #[test]
+    fn strip_annotations() {
+        let text = "{Intro} 1. e4 {[%clk 0:05:00] best} e5 (1... c5 {Sicilian}) 2. Nf3 *";
+        let game = pgn::parse_pgn(text).unwrap();
+
+        let options = normalize::NormalizeOptions {
+            strip_clocks: true,
+            ..Default::default()
+        };
+        let normalized = normalize::normalize_game(&game, &options).unwrap();
+        assert_eq!(normalized.moves[0].comments, vec!["best"]);
+        assert_eq!(normalized.moves[1].variations.len(), 1);
+
+        let options = normalize::NormalizeOptions {
+            strip_comments: true,
+            strip_variations: true,
+            ..Default::default()
+        };
+        let normalized = normalize::normalize_game(&game, &options).unwrap();
+        assert_eq!(normalized.comments.len(), 0);
+        assert_eq!(normalized.moves[0].comments, vec!["[%clk 0:05:00]"]);
+        assert_eq!(normalized.moves[1].variations.len(), 0);
+    }
+
+    // moves in variations are checked against the position they branch from
+    #[test]
+    fn illegal_variation() {
+        let game = pgn::parse_pgn("1. e4 e5 (1... Nf3) 2. Nf3 *").unwrap();
+        let normalized = normalize::normalize_game(&game, &normalize::NormalizeOptions::default());
+        assert_eq!(normalized, Err(validate::ValidationIssue::IllegalMove {
+            move_number: 1,
+            player: game::Player::Black,
+            san: "Nf3".to_string(),
+            error: moves::MoveError::InvalidMove,
+        }));
+    }
+
+    // normalising twice gives the same text
+    #[test]
+    fn normalize_database() {
+        let text = concat!(
+            "[Event \"a\"]\n\n1. e4  e5\n 2. Ngf3 *\n\n",
+            "[Event \"b\"]\n\n1. e4 e4 *\n\n",
+            "[Event \"c\"]\n\n1. d4 {Queen's pawn} 1-0\n"
+        );
+        let options = normalize::NormalizeOptions::default();
+        let mut output: Vec<u8> = Vec::new();
+        let reports = normalize::normalize_pgn(
+            std::io::Cursor::new(text),
+            &mut output,
+            &options
+        ).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].game, 2);
+
+        let mut again: Vec<u8> = Vec::new();
+        normalize::normalize_pgn(std::io::Cursor::new(output.clone()), &mut again, &options).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), String::from_utf8(output).unwrap());
+    }
+}
@@ -3,6 +3,8 @@ mod notation_tests {
     use crate::game;
     use crate::notation;
     use crate::board;
+    use crate::moves;
+    use crate::pieces;
 
     #[test]
     fn pawn_move_notation() {
@@ -29,4 +31,65 @@ mod notation_tests {
         assert_eq!(notation::parse_notation(&board, &game::Player::Black, "dxe4").is_err(), false);
         assert_eq!(notation::parse_notation(&board, &game::Player::Black, "xa6").is_err(), true);
     }
+
+    #[test]
+    fn castle_notation() {
+        let board = crate::fen::parse_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let mv = notation::parse_notation(&board, &game::Player::White, "O-O").unwrap();
+        assert_eq!(mv, moves::PieceMove::new(pieces::PieceType::King, 4, 0, 6, 0));
+        let mv = notation::parse_notation(&board, &game::Player::Black, "O-O-O+").unwrap();
+        assert_eq!(mv, moves::PieceMove::new(pieces::PieceType::King, 4, 7, 2, 7));
+    }
+
+    // castling without a king on its starting square must not conjure one up
+    #[test]
+    fn castle_without_king() {
+        let mut board = crate::fen::parse_fen("4k3/8/8/8/8/8/8/3K3R w - - 0 1").unwrap();
+        assert_eq!(notation::parse_notation(&board, &game::Player::White, "O-O").is_err(), true);
+        assert_eq!(board.execute_notation(None, "O-O").is_err(), true);
+
+        // nor can a king two squares along any other rank be played as a castle
+        let castle = moves::PieceMove::new(pieces::PieceType::King, 4, 0, 6, 0);
+        assert_eq!(board.make_move(None, castle).is_err(), true);
+        assert_eq!(board.apply_move(game::Player::White, castle).is_err(), true);
+        assert_eq!(crate::fen::to_fen(&board), "4k3/8/8/8/8/8/8/3K3R w - - 0 1");
+    }
+
+    #[test]
+    fn san_generation() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+
+        let san = |board: &board::Board, notation: &str| {
+            let player = match board.get_state() {
+                game::GameState::Playing(p) => *p,
+                _ => panic!("game over"),
+            };
+            let mv = notation::parse_notation(board, &player, notation).unwrap();
+            return notation::to_san(board, mv);
+        };
+
+        // redundant disambiguation and missing capture marks are dropped
+        assert_eq!(san(&board, "Ngf3"), "Nf3");
+        assert_eq!(san(&board, "e2e4"), "e4");
+        board.execute_notation(None, "e4").unwrap();
+        board.execute_notation(None, "d5").unwrap();
+        assert_eq!(san(&board, "ed5"), "exd5");
+        assert_eq!(san(&board, "Bb5"), "Bb5+");
+
+        // two knights that can reach the same square
+        let board = crate::fen::parse_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert_eq!(san(&board, "Nbd2"), "Nbd2");
+        let board = crate::fen::parse_fen("4k3/8/8/8/8/1N6/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(san(&board, "N1d2"), "N1d2");
+        let board = crate::fen::parse_fen("8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1").unwrap();
+        assert_eq!(san(&board, "Qh4e1"), "Qh4e1");
+
+        // castling, promotion and mate
+        let board = crate::fen::parse_fen("3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert_eq!(san(&board, "O-O"), "O-O");
+        assert_eq!(san(&board, "O-O-O"), "O-O-O+");
+        let board = crate::fen::parse_fen("7k/1P6/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(san(&board, "b8=Q"), "b8=Q#");
+    }
 }
@@ -0,0 +1,267 @@
+#[cfg(test)]
+mod pgn_tests {
+    use crate::fen;
+    use crate::game;
+    use crate::pgn;
+
+    #[test]
+    fn parse_tags() {
+        let game = pgn::parse_pgn(
+            "[Event \"The \\\"Match\\\"\"]\n[White \"Back\\\\slash\"]\n[Result \"*\"]\n\n*"
+        ).unwrap();
+        assert_eq!(game.get_tag("Event"), Some("The \"Match\""));
+        assert_eq!(game.get_tag("White"), Some("Back\\slash"));
+        assert_eq!(game.get_tag("Black"), None);
+        assert_eq!(game.tags.len(), 3);
+        assert_eq!(game.moves.len(), 0);
+        assert_eq!(game.result, "*");
+    }
+
+    #[test]
+    fn parse_comments() {
+        let game = pgn::parse_pgn(
+            "{Opening comment} 1. e4 {best by test 1. d4 (2. c4)} e5 ; rest of line 2. Nf3\n2. Nf3 1-0"
+        ).unwrap();
+        assert_eq!(game.comments, vec!["Opening comment"]);
+        assert_eq!(game.main_line(), vec!["e4", "e5", "Nf3"]);
+        assert_eq!(game.moves[0].comments, vec!["best by test 1. d4 (2. c4)"]);
+        assert_eq!(game.moves[1].comments, vec!["rest of line 2. Nf3"]);
+        assert_eq!(game.result, "1-0");
+
+        // lines starting with % are ignored
+        let game = pgn::parse_pgn("%escaped 1. d4\n1. e4 *").unwrap();
+        assert_eq!(game.main_line(), vec!["e4"]);
+    }
+
+    #[test]
+    fn parse_annotations() {
+        let game = pgn::parse_pgn("1. e4! e5?! 2. Qh5?? $32 Nc6!! 3. Bc4!? ?? 0-1").unwrap();
+        assert_eq!(game.main_line(), vec!["e4", "e5", "Qh5", "Nc6", "Bc4"]);
+        assert_eq!(game.moves[0].nags, vec![1]);
+        assert_eq!(game.moves[1].nags, vec![6]);
+        assert_eq!(game.moves[2].nags, vec![4, 32]);
+        assert_eq!(game.moves[3].nags, vec![3]);
+        assert_eq!(game.moves[4].nags, vec![5, 4]);
+    }
+
+    #[test]
+    fn parse_variations() {
+        let game = pgn::parse_pgn(
+            "1. e4 e5 (1... c5 2. Nf3 (2. c3 {Alapin}) 2... d6) ({French} 1... e6) 2. Nf3 *"
+        ).unwrap();
+        assert_eq!(game.main_line(), vec!["e4", "e5", "Nf3"]);
+
+        let variations = &game.moves[1].variations;
+        assert_eq!(variations.len(), 2);
+        assert_eq!(variations[0].len(), 3);
+        assert_eq!(variations[0][0].san, "c5");
+        assert_eq!(variations[0][1].variations[0][0].san, "c3");
+        assert_eq!(variations[0][1].variations[0][0].comments, vec!["Alapin"]);
+        assert_eq!(variations[1][0].san, "e6");
+        assert_eq!(variations[1][0].starting_comments, vec!["French"]);
+    }
+
+    #[test]
+    fn parse_multiple_games() {
+        let games = pgn::parse_pgn_games(
+            "[Event \"1\"]\n\n1. e4 e5 1-0\n\n[Event \"2\"]\n\n1. d4 d5 1/2-1/2\n"
+        ).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].get_tag("Event"), Some("2"));
+        assert_eq!(games[1].main_line(), vec!["d4", "d5"]);
+        assert_eq!(games[1].result, "1/2-1/2");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = pgn::parse_pgn("[Event \"x\"]\n\n1. e4 {unterminated\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 7));
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnterminatedComment);
+
+        let error = pgn::parse_pgn("1. e4 (1. d4 e5 *").unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnexpectedToken("*".to_string()));
+        assert_eq!((error.line, error.column), (1, 17));
+
+        let error = pgn::parse_pgn("1. e4 e5\n2. Nf3 ) *").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 8));
+
+        let error = pgn::parse_pgn("[Event \"x]\n1. e4 *").unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnterminatedString);
+
+        let error = pgn::parse_pgn("1. e4 e5").unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnexpectedEnd);
+
+        let error = pgn::parse_pgn("1. e4 & *").unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnexpectedCharacter('&'));
+    }
+
+    #[test]
+    fn export_game() {
+        let mut game = game::Game::new();
+        game.white = "Anderssen".to_string();
+        game.black = "Kieseritzky".to_string();
+        for mv in ["e4", "e5", "f4", "exf4"] {
+            game.play_notation(mv).unwrap();
+        }
+        game.set_clock(std::time::Duration::from_secs(3725));
+
+        assert_eq!(
+            pgn::write_pgn(&pgn::from_game(&game)),
+            concat!(
+                "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n",
+                "[White \"Anderssen\"]\n[Black \"Kieseritzky\"]\n[Result \"*\"]\n\n",
+                "1. e4 e5 2. f4 exf4 {[%clk 1:02:05]} *\n"
+            )
+        );
+
+        game.resign(game::Player::White);
+        let exported = pgn::write_pgn(&pgn::from_game(&game));
+        assert_eq!(exported.contains("[Result \"0-1\"]"), true);
+        assert_eq!(exported.ends_with("exf4 {[%clk 1:02:05]} 0-1\n"), true);
+    }
+
+    // games from a custom position carry a FEN tag and start at the right move number
+    #[test]
+    fn export_custom_start() {
+        let position = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let mut game = game::Game::from_board(fen::parse_fen(position).unwrap());
+        game.play_notation("Bb5").unwrap();
+        game.play_notation("a6").unwrap();
+
+        let exported = pgn::write_pgn(&pgn::from_game(&game));
+        assert_eq!(exported.contains("[SetUp \"1\"]\n[FEN \"r1bqkbnr"), true);
+        assert_eq!(exported.ends_with("\n\n3. Bb5 a6 *\n"), true);
+
+        let mut game = game::Game::from_board(fen::parse_fen(&game.get_positions()[1]).unwrap());
+        game.play_notation("a6").unwrap();
+        let exported = pgn::write_pgn(&pgn::from_game(&game));
+        assert_eq!(exported.ends_with("\n\n3... a6 *\n"), true);
+    }
+
+    // long games are wrapped at 80 columns and read back the same
+    #[test]
+    fn export_round_trip() {
+        let contents = std::fs::read_to_string("./games/3.pgn").unwrap();
+        let original = pgn::parse_pgn(&contents).unwrap();
+        let mut game = game::Game::new();
+        for san in original.main_line() {
+            game.play_notation(san).unwrap();
+        }
+        game.play_notation(&original.result).unwrap();
+
+        let exported = pgn::write_pgn(&pgn::from_game(&game));
+        assert_eq!(exported.lines().all(|line| line.len() <= 80), true);
+        let reparsed = pgn::parse_pgn(&exported).unwrap();
+        assert_eq!(reparsed.main_line(), original.main_line());
+        assert_eq!(reparsed.result, "1-0");
+    }
+
+    // comments, annotations and variations survive being written out
+    #[test]
+    fn export_annotations() {
+        let text =
+            "[Event \"x\"]\n\n{Start} 1. e4 $1 {King pawn} 1... e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3 *\n";
+        let game = pgn::parse_pgn(text).unwrap();
+        assert_eq!(pgn::write_pgn(&game), text);
+    }
+
+    // games are streamed one at a time and a broken game doesn't stop the rest
+    #[test]
+    fn stream_games() {
+        let text = concat!(
+            "[Event \"one\"]\n\n1. e4 {a comment\n[not a tag]\nstill} e5 1-0\n\n",
+            "[Event \"two\"]\n\n1. d4 (1. c4\n\n",
+            "[Event \"three\"]\n\n1. Nf3 {never closed\n\n",
+            "[Event \"four\"]\n\n1. c4 0-1\n"
+        );
+        let results: Vec<Result<pgn::PgnGame, pgn::PgnError>> = pgn::PgnReader::new(
+            std::io::Cursor::new(text)
+        ).collect();
+        assert_eq!(results.len(), 4);
+
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.get_tag("Event"), Some("one"));
+        assert_eq!(first.main_line(), vec!["e4", "e5"]);
+
+        let error = results[1].as_ref().unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnterminatedVariation);
+        // the variation runs on until the tag that starts the next game
+        assert_eq!((error.line, error.column), (11, 1));
+
+        let error = results[2].as_ref().unwrap_err();
+        assert_eq!(error.kind, pgn::PgnErrorKind::UnterminatedComment);
+        // the comment is reported where it opens
+        assert_eq!((error.line, error.column), (13, 8));
+
+        let last = results[3].as_ref().unwrap();
+        assert_eq!(last.get_tag("Event"), Some("four"));
+        assert_eq!(last.result, "0-1");
+    }
+
+    // without tags a result ends the game, so a broken game is read on its own and the rest keep their numbers
+    #[test]
+    fn stream_tagless_games() {
+        let text = "1. e4 {not 1-0 yet} e5 1-0\n1. d4 ) d5 0-1\n1. c4 c5 1/2-1/2 1. Nf3 ) *\n1. g3 (1. b3 *) *\n";
+        let mut games = pgn::PgnReader::new(std::io::Cursor::new(text));
+        let mut results = Vec::new();
+        while let Some(result) = games.next() {
+            results.push((games.game_number(), result));
+        }
+        assert_eq!(results.len(), 5);
+        assert_eq!(results.iter().map(|(number, _)| *number).collect::<Vec<usize>>(), vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(results[0].1.as_ref().unwrap().main_line(), vec!["e4", "e5"]);
+        let error = results[1].1.as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (2, 7));
+        assert_eq!(results[2].1.as_ref().unwrap().main_line(), vec!["c4", "c5"]);
+        // a game that starts part way along a line keeps its columns
+        let error = results[3].1.as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (3, 25));
+        // a result inside a variation doesn't end the game, so the whole game is reported once
+        let error = results[4].1.as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (4, 14));
+    }
+
+    // every game in the sample files can be streamed from disk
+    #[test]
+    fn stream_files() {
+        for i in 1..=5 {
+            let games: Vec<pgn::PgnGame> = pgn::read_pgn_file(&format!("./games/{i}.pgn"))
+                .unwrap()
+                .map(|g| g.unwrap())
+                .collect();
+            assert_eq!(games.len(), 1);
+        }
+    }
+
+    // games with variations, comments, NAGs and clocks map one-to-one onto a game tree
+    #[test]
+    fn game_tree_round_trip() {
+        let text = concat!(
+            "[Event \"x\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n",
+            "[White \"?\"]\n[Black \"?\"]\n[Result \"1-0\"]\n\n",
+            "{Start} 1. e4 $1 {[%clk 0:05:00] King pawn} 1... e5 (1... c5 2. Nf3 (2. c3 {Alapin}))\n",
+            "({Or} 1... e6) 2. Nf3 1-0\n"
+        );
+        let original = pgn::parse_pgn(text).unwrap();
+        let game = pgn::to_game(&original).unwrap();
+        assert_eq!(game.event, "x");
+        assert_eq!(game.get_moves().len(), 3);
+        assert_eq!(game.get_moves()[0].clock, Some(std::time::Duration::from_secs(300)));
+        assert_eq!(game.get_result(), &game::GameState::Won(game::Player::White));
+        assert_eq!(game.termination, game::Termination::Resignation);
+        assert_eq!(pgn::from_game(&game), original);
+
+        let error = pgn::to_game(&pgn::parse_pgn("1. e4 e5 (1... Ke7) *").unwrap());
+        assert_eq!(error.is_err(), true);
+
+        // a game built by hand may have no result, or one that isn't a result token
+        let game = pgn::to_game(&pgn::PgnGame::default()).unwrap();
+        assert_eq!(game.get_result(), &game::GameState::Playing(game::Player::White));
+        assert_eq!(game.termination, game::Termination::Unterminated);
+        let mut unknown = pgn::parse_pgn("1. e4 *").unwrap();
+        unknown.result = "resigns".to_string();
+        let game = pgn::to_game(&unknown).unwrap();
+        assert_eq!(game.get_result(), &game::GameState::Playing(game::Player::Black));
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod pgn_validate_tests {
+    use crate::game;
+    use crate::moves;
+    use crate::pgn;
+    use crate::validate;
+
+    #[test]
+    fn valid_game() {
+        let game = pgn::parse_pgn(
+            "[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game), vec![]);
+    }
+
+    // the first illegal move is reported with its move number and notation
+    #[test]
+    fn illegal_move() {
+        let game = pgn::parse_pgn(
+            "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 Ke6 4. Bxc6 *"
+        ).unwrap();
+        let issues = validate::validate_game(&game);
+        assert_eq!(issues, vec![
+            validate::ValidationIssue::IllegalMove {
+                move_number: 3,
+                player: game::Player::Black,
+                san: "Ke6".to_string(),
+                error: moves::MoveError::InvalidMove,
+            }
+        ]);
+        assert_eq!(issues[0].to_string(), "move 3... Ke6: Invalid move!");
+    }
+
+    // every move is checked against the legal moves, including castling and promotion
+    #[test]
+    fn illegal_castle_and_promotion() {
+        let game = pgn::parse_pgn(
+            "[FEN \"4k3/8/8/8/8/8/8/3K3R w - - 0 1\"]\n[Result \"*\"]\n\n1. O-O *"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game), vec![
+            validate::ValidationIssue::IllegalMove {
+                move_number: 1,
+                player: game::Player::White,
+                san: "O-O".to_string(),
+                error: moves::MoveError::InvalidMove,
+            }
+        ]);
+
+        // the king has already moved, so it has lost its castling right
+        let game = pgn::parse_pgn(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. Ke2 Nf6 5. Ke1 d6 6. O-O *"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game).len(), 1);
+
+        let game = pgn::parse_pgn(
+            "[FEN \"4k3/1P6/8/8/8/8/8/4K3 w - - 0 1\"]\n[Result \"*\"]\n\n1. b8 Kd7 *"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game), vec![
+            validate::ValidationIssue::IllegalMove {
+                move_number: 1,
+                player: game::Player::White,
+                san: "b8".to_string(),
+                error: moves::MoveError::InvalidPromotion,
+            }
+        ]);
+        let game = pgn::parse_pgn(
+            "[FEN \"4k3/1P6/8/8/8/8/8/4K3 w - - 0 1\"]\n[Result \"*\"]\n\n1. b8=Q Kd7 *"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game), vec![]);
+    }
+
+    #[test]
+    fn unparsable_move() {
+        let game = pgn::parse_pgn("1. e4 e5 2. Zz9 *").unwrap();
+        let issues = validate::validate_game(&game);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].to_string(), "move 2. Zz9: Invalid notation syntax!");
+    }
+
+    // a mated side cannot have won, and the tag must agree with the movetext
+    #[test]
+    fn result_mismatch() {
+        let game = pgn::parse_pgn(
+            "[Result \"1-0\"]\n\n1. f3 e5 2. g4 Qh4# 1/2-1/2"
+        ).unwrap();
+        assert_eq!(validate::validate_game(&game), vec![
+            validate::ValidationIssue::ResultMismatch {
+                tag: "1-0".to_string(),
+                expected: "1/2-1/2".to_string(),
+            },
+            validate::ValidationIssue::ResultMismatch {
+                tag: "1-0".to_string(),
+                expected: "0-1".to_string(),
+            }
+        ]);
+
+        // a resignation can't be checked against the board
+        let game = pgn::parse_pgn("[Result \"1-0\"]\n\n1. e4 1-0").unwrap();
+        assert_eq!(validate::validate_game(&game), vec![]);
+    }
+
+    // every game in a database is checked, even after a broken one
+    #[test]
+    fn validate_database() {
+        let text = concat!(
+            "[Event \"a\"]\n[Result \"*\"]\n\n1. e4 e5 *\n\n",
+            "[Event \"b\"]\n[Result \"*\"]\n\n1. e4 {unclosed *\n\n",
+            "[Event \"c\"]\n[Result \"*\"]\n\n1. e4 e4 *\n"
+        );
+        let reports = validate::validate_pgn(std::io::Cursor::new(text));
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].game, 2);
+        assert_eq!(matches!(reports[0].issue, validate::ValidationIssue::Syntax(_)), true);
+        assert_eq!(reports[1].game, 3);
+        assert_eq!(reports[1].to_string(), "Game 3, move 1... e4: Invalid move!");
+    }
+}
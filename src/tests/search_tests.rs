@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod search_tests {
+    use crate::database;
+    use crate::game;
+    use crate::pieces;
+    use crate::search;
+
+    const GAMES: &str = concat!(
+        "[White \"a\"]\n\n1. e4 c6 2. d4 d5 3. Nc3 dxe4 *\n\n",
+        "[White \"b\"]\n\n1. d4 d5 2. e4 c6 3. Nc3 dxe4 4. Nxe4 *\n\n",
+        "[White \"c\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n"
+    );
+
+    #[test]
+    fn parse_patterns() {
+        let (white, black) = search::parse_material("KRPvKR").unwrap();
+        assert_eq!(white[pieces::PieceType::Pawn as usize], 1);
+        assert_eq!(white[pieces::PieceType::Rook as usize], 1);
+        assert_eq!(black[pieces::PieceType::Pawn as usize], 0);
+        assert_eq!(search::parse_material("KRPKR"), None);
+        assert_eq!(search::parse_material("KXvK"), None);
+
+        assert_eq!(
+            search::parse_piece_square("pd5"),
+            Some((3, 4, Some((game::Player::Black, pieces::PieceType::Pawn))))
+        );
+        assert_eq!(search::parse_piece_square("d4"), Some((3, 3, None)));
+        assert_eq!(search::parse_piece_square("Xd4"), None);
+
+        let (white, black) = search::parse_pawns("4k3/pp6/8/8/3P4/8/8/4K3").unwrap();
+        assert_eq!(white, vec![(3, 3)]);
+        assert_eq!(black, vec![(0, 6), (1, 6)]);
+    }
+
+    // the same position is found however it was reached (transpositions included)
+    #[test]
+    fn search_position() {
+        let query = search::PositionQuery {
+            fen: Some("rnbqkbnr/pp2pppp/2p5/8/3Pp3/2N5/PPP2PPP/R1BQKBNR w KQkq - 0 4".to_string()),
+            ..Default::default()
+        };
+        let hits = search::search_pgn(&query, std::io::Cursor::new(GAMES));
+        let found: Vec<(usize, usize)> = hits
+            .iter()
+            .map(|hit| (hit.game, hit.ply))
+            .collect();
+        assert_eq!(found, vec![(1, 6), (2, 6)]);
+        assert_eq!(hits[1].tags, vec![("White".to_string(), "b".to_string())]);
+    }
+
+    // an en passant square is matched whether or not a capture is possible there
+    #[test]
+    fn search_position_en_passant() {
+        let position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let query = search::PositionQuery {
+            fen: search::parse_position(position),
+            ..Default::default()
+        };
+        assert_eq!(query.fen, Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_string()));
+        let hits = search::search_pgn(&query, std::io::Cursor::new("1. e4 e5 *\n"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].ply, 1);
+
+        assert_eq!(search::parse_position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"), None);
+    }
+
+    #[test]
+    fn search_patterns() {
+        // a white knight on e4 after ...dxe4 has been taken back
+        let query = search::PositionQuery {
+            pieces: vec![search::parse_piece_square("Ne4").unwrap()],
+            ..Default::default()
+        };
+        let hits = search::search_pgn(&query, std::io::Cursor::new(GAMES));
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].game, hits[0].ply), (2, 7));
+
+        // a pawn each has been captured, and it is black to move
+        let query = search::PositionQuery {
+            material: search::parse_material("KQRRBBNNPPPPPPPvKQRRBBNNPPPPPPP"),
+            side_to_move: Some(game::Player::Black),
+            ..Default::default()
+        };
+        let hits = search::search_pgn(&query, std::io::Cursor::new(GAMES));
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].game, hits[0].ply), (2, 7));
+
+        // the pawn structure after 1. e4 e5
+        let query = search::PositionQuery {
+            pawns: search::parse_pawns("8/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/8"),
+            ..Default::default()
+        };
+        let hits = search::search_pgn(&query, std::io::Cursor::new(GAMES));
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].game, hits[0].ply), (3, 2));
+    }
+
+    // a binary database gives the same results as the PGN it was made from
+    #[test]
+    fn search_database() {
+        let mut bytes: Vec<u8> = Vec::new();
+        database::pgn_to_database(std::io::Cursor::new(GAMES), &mut bytes).unwrap();
+        let mut db = database::Database::open(std::io::Cursor::new(bytes)).unwrap();
+
+        let query = search::PositionQuery {
+            pieces: vec![
+                search::parse_piece_square("pc6").unwrap(),
+                search::parse_piece_square("d5").unwrap()
+            ],
+            ..Default::default()
+        };
+        let hits = search::search_database(&query, &mut db).unwrap();
+        assert_eq!(hits, search::search_pgn(&query, std::io::Cursor::new(GAMES)));
+        let found: Vec<(usize, usize)> = hits
+            .iter()
+            .map(|hit| (hit.game, hit.ply))
+            .collect();
+        assert_eq!(found, vec![(1, 2), (2, 6)]);
+    }
+}
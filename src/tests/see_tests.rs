@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod see_tests {
+    use crate::board;
+    use crate::game;
+    use crate::moves;
+    use crate::pieces;
+
+    // capturing an undefended piece wins it outright
+    #[test]
+    fn see_undefended() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::Rook, 0, 0);
+        board.place_piece(game::Player::Black, pieces::PieceType::Knight, 0, 5);
+
+        let mv = moves::PieceMove::new(pieces::PieceType::Rook, 0, 0, 0, 5);
+        assert_eq!(board.see(mv), pieces::PieceType::Knight.value());
+    }
+
+    // capturing a defended pawn with the queen loses the queen for a pawn
+    #[test]
+    fn see_losing_capture() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::Queen, 3, 0);
+        board.place_piece(game::Player::Black, pieces::PieceType::Pawn, 3, 5);
+        board.place_piece(game::Player::Black, pieces::PieceType::Pawn, 4, 6);
+
+        let mv = moves::PieceMove::new(pieces::PieceType::Queen, 3, 0, 3, 5);
+        assert_eq!(
+            board.see(mv),
+            pieces::PieceType::Pawn.value() - pieces::PieceType::Queen.value()
+        );
+    }
+
+    // pawn takes a defended knight: the pawn is lost but the knight is still won
+    #[test]
+    fn see_winning_exchange() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::Pawn, 4, 3);
+        board.place_piece(game::Player::Black, pieces::PieceType::Knight, 3, 4);
+        board.place_piece(game::Player::Black, pieces::PieceType::Pawn, 2, 5);
+
+        let mv = moves::PieceMove::new(pieces::PieceType::Pawn, 4, 3, 3, 4);
+        assert_eq!(
+            board.see(mv),
+            pieces::PieceType::Knight.value() - pieces::PieceType::Pawn.value()
+        );
+    }
+
+    // the rook behind the first rook joins the exchange once the front rook has captured
+    #[test]
+    fn see_x_ray() {
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::Rook, 0, 0);
+        board.place_piece(game::Player::White, pieces::PieceType::Rook, 0, 1);
+        board.place_piece(game::Player::Black, pieces::PieceType::Rook, 0, 7);
+        board.place_piece(game::Player::Black, pieces::PieceType::Knight, 0, 5);
+
+        // Rxa6 Rxa6 Rxa6 wins a knight for nothing
+        let mv = moves::PieceMove::new(pieces::PieceType::Rook, 0, 1, 0, 5);
+        assert_eq!(board.see(mv), pieces::PieceType::Knight.value());
+
+        // without the back rook, the exchange is a rook for a knight
+        board.clear_square(0, 0);
+        assert_eq!(
+            board.see(mv),
+            pieces::PieceType::Knight.value() - pieces::PieceType::Rook.value()
+        );
+    }
+
+    // en passant captures a pawn from a different square than the destination
+    #[test]
+    fn see_en_passant() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        board.execute_notation(Some(game::Player::White), "e4").unwrap();
+        board.execute_notation(Some(game::Player::Black), "a6").unwrap();
+        board.execute_notation(Some(game::Player::White), "e5").unwrap();
+        board.execute_notation(Some(game::Player::Black), "d5").unwrap();
+
+        let mv = moves::PieceMove::new(pieces::PieceType::Pawn, 4, 4, 3, 5);
+        assert_eq!(board.is_capture(mv), true);
+        assert_eq!(board.see(mv), 0);
+    }
+}
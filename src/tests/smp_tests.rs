@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod smp_tests {
+    use crate::engine;
+    use crate::fen;
+    use crate::moves;
+    use crate::pieces;
+    use crate::transposition;
+    use std::time::Duration;
+
+    const MIDDLEGAME: &str = "r1bq1rk1/ppp2ppp/2np1n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 7";
+
+    // boards can be handed to other threads
+    #[test]
+    fn board_is_send_and_sync() {
+        fn shareable<T: Send + Sync>(_: &T) {}
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        shareable(&board);
+        let expected = board.legal_moves();
+        let handle = std::thread::spawn(move || board.legal_moves());
+        assert_eq!(handle.join().unwrap(), expected);
+    }
+
+    // one thread searches the same tree every time
+    #[test]
+    fn single_thread_is_deterministic() {
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        let limits = engine::SearchLimits { depth: 3, ..Default::default() };
+        let first = engine::Engine::new().search(&board, &limits);
+        let second = engine::Engine::new().search(&board, &limits);
+        assert_eq!((first.best_move, first.score, &first.pv, first.nodes), (second.best_move, second.score, &second.pv, second.nodes));
+    }
+
+    #[test]
+    fn several_threads() {
+        let mut engine = engine::Engine::new();
+        engine.set_threads(4);
+        assert_eq!(engine.get_threads(), 4);
+
+        let result = engine.search(
+            &fen::parse_fen("3r2k1/5ppp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap(),
+            &engine::SearchLimits { depth: 4, ..Default::default() }
+        );
+        assert_eq!(result.score, engine::Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+
+        // the helpers stop with the main thread, whatever limit it reached
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        let limits = engine::SearchLimits {
+            depth: engine::MAX_DEPTH,
+            move_time: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+        let result = engine.search(&board, &limits);
+        assert_eq!(result.elapsed < Duration::from_secs(1), true);
+        assert_eq!(board.legal_moves().contains(&result.best_move.unwrap()), true);
+
+        engine.set_threads(0);
+        assert_eq!(engine.get_threads(), 1);
+    }
+
+    // threads write to the table at the same time without corrupting what the others read
+    #[test]
+    fn shared_table() {
+        let table = transposition::TranspositionTable::new(1);
+        let promotion = moves::PieceMove::new(pieces::PieceType::Knight, 6, 6, 7, 7);
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..10000u64 {
+                        let key = (i * 4 + thread).wrapping_mul(0x9e3779b97f4a7c15);
+                        table.store(key, (i % 20) as u32, transposition::Bound::Lower, -(i as i32), Some(promotion), 0);
+                    }
+                });
+            }
+        });
+
+        let mut found = 0;
+        for thread in 0..4u64 {
+            for i in 0..10000u64 {
+                let key = (i * 4 + thread).wrapping_mul(0x9e3779b97f4a7c15);
+                if let Some(entry) = table.probe(key, 0) {
+                    assert_eq!(entry.score, -(i as i32));
+                    assert_eq!(entry.depth, (i % 20) as u32);
+                    assert_eq!(entry.best_move, Some(promotion));
+                    found += 1;
+                }
+            }
+        }
+        assert_eq!(found > 0, true);
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod time_tests {
+    use crate::engine;
+    use crate::fen;
+    use std::time::Duration;
+
+    const MIDDLEGAME: &str = "r1bq1rk1/ppp2ppp/2np1n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 7";
+
+    #[test]
+    fn time_budget() {
+        let untimed = engine::SearchLimits::default();
+        assert_eq!(untimed.time_budget(), None);
+
+        // a fixed time per move keeps a little back for overhead
+        let fixed = engine::SearchLimits { move_time: Some(Duration::from_secs(1)), ..Default::default() };
+        assert_eq!(fixed.time_budget(), Some((Duration::from_millis(950), Duration::from_millis(950))));
+
+        // a share of the clock plus most of the increment, with room to overrun
+        let clock = engine::SearchLimits {
+            time_left: Some(Duration::from_millis(60050)),
+            increment: Duration::from_secs(2),
+            ..Default::default()
+        };
+        let (soft, hard) = clock.time_budget().unwrap();
+        assert_eq!(soft, Duration::from_millis(3500));
+        assert_eq!(hard, Duration::from_millis(10500));
+
+        // with one move to the time control, most of the clock may be used, but never all of it
+        let last_move = engine::SearchLimits {
+            time_left: Some(Duration::from_millis(10050)),
+            moves_to_go: Some(1),
+            ..Default::default()
+        };
+        let (soft, hard) = last_move.time_budget().unwrap();
+        assert_eq!(hard, Duration::from_millis(7500));
+        assert_eq!(soft, hard);
+    }
+
+    // a timed search returns close to its deadline with the result of a completed iteration
+    #[test]
+    fn move_time() {
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        let limits = engine::SearchLimits {
+            depth: engine::MAX_DEPTH,
+            move_time: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+        let mut iterations = Vec::new();
+        let result = engine::Engine::new().search_with(&board, &limits, |iteration| {
+            iterations.push(iteration.clone());
+        });
+        assert_eq!(result.elapsed < Duration::from_millis(600), true);
+        assert_eq!(result.depth >= 1, true);
+        let last = iterations.last().unwrap();
+        assert_eq!((result.best_move, result.depth, &result.pv), (last.best_move, last.depth, &last.pv));
+    }
+
+    // even when no iteration completes, there is a legal move to play
+    #[test]
+    fn hard_limit() {
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        let limits = engine::SearchLimits {
+            depth: engine::MAX_DEPTH,
+            move_time: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let result = engine::Engine::new().search(&board, &limits);
+        assert_eq!(result.elapsed < Duration::from_millis(200), true);
+        assert_eq!(board.legal_moves().contains(&result.best_move.unwrap()), true);
+    }
+
+    // the stop signal can be set from another thread while the search runs
+    #[test]
+    fn stop_signal() {
+        let board = fen::parse_fen(MIDDLEGAME).unwrap();
+        let mut engine = engine::Engine::new();
+        let stop = engine.stop_signal();
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        let limits = engine::SearchLimits { depth: engine::MAX_DEPTH, ..Default::default() };
+        let result = engine.search(&board, &limits);
+        stopper.join().unwrap();
+
+        assert_eq!(result.elapsed < Duration::from_secs(2), true);
+        assert_eq!(result.depth < engine::MAX_DEPTH, true);
+        assert_eq!(board.legal_moves().contains(&result.best_move.unwrap()), true);
+
+        // the next search starts afresh
+        let result = engine.search(&board, &engine::SearchLimits { depth: 2, ..Default::default() });
+        assert_eq!(result.depth, 2);
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod transposition_tests {
+    use crate::board;
+    use crate::engine;
+    use crate::fen;
+    use crate::moves;
+    use crate::pieces;
+    use crate::transposition;
+
+    fn play(moves: &[&str]) -> board::Board {
+        let mut board = board::Board::new();
+        board.reset_board();
+        for mv in moves {
+            board.execute_notation(None, mv).unwrap();
+        }
+        return board;
+    }
+
+    // the same position reached by different move orders gets the same key
+    #[test]
+    fn transpositions() {
+        let first = play(&["e4", "e5", "Nf3", "Nc6"]);
+        let second = play(&["Nf3", "Nc6", "e4", "e5"]);
+        assert_eq!(transposition::hash_position(&first), transposition::hash_position(&second));
+        assert_eq!(
+            transposition::hash_position(&first),
+            transposition::hash_position(&fen::parse_fen(&fen::to_fen(&first)).unwrap())
+        );
+
+        // but not when castling rights were lost on the way
+        let moved_king = play(&["e4", "e5", "Ke2", "Ke7", "Ke1", "Ke8"]);
+        let start = play(&["e4", "e5"]);
+        assert_eq!(
+            transposition::hash_position(&moved_king) == transposition::hash_position(&start),
+            false
+        );
+    }
+
+    #[test]
+    fn side_to_move_and_en_passant() {
+        let white = fen::parse_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let black = fen::parse_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(transposition::hash_position(&white) == transposition::hash_position(&black), false);
+
+        let en_passant = fen::parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let no_en_passant = fen::parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2").unwrap();
+        assert_eq!(
+            transposition::hash_position(&en_passant) == transposition::hash_position(&no_en_passant),
+            false
+        );
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let table = transposition::TranspositionTable::new(1);
+        assert_eq!(table.capacity() > 1000, true);
+        let mv = moves::PieceMove::new(pieces::PieceType::Pawn, 4, 1, 4, 3);
+
+        table.store(42, 5, transposition::Bound::Lower, 35, Some(mv), 3);
+        let entry = table.probe(42, 7).unwrap();
+        assert_eq!(entry.depth, 5);
+        assert_eq!(entry.bound, transposition::Bound::Lower);
+        assert_eq!(entry.score, 35);
+        assert_eq!(entry.best_move, Some(mv));
+        assert_eq!(table.probe(43, 0), None);
+
+        // storing without a move keeps the move the position had
+        table.store(42, 6, transposition::Bound::Upper, -10, None, 0);
+        assert_eq!(table.probe(42, 0).unwrap().best_move, Some(mv));
+
+        table.clear();
+        assert_eq!(table.probe(42, 0), None);
+    }
+
+    // mates are stored as a distance from the position and read back relative to the new root
+    #[test]
+    fn mate_scores() {
+        let table = transposition::TranspositionTable::new(1);
+        // found 4 plies from the root, mating 3 plies after that
+        table.store(7, 3, transposition::Bound::Exact, engine::MATE - 7, None, 4);
+        assert_eq!(table.probe(7, 4).unwrap().score, engine::MATE - 7);
+        assert_eq!(table.probe(7, 0).unwrap().score, engine::MATE - 3);
+        assert_eq!(table.probe(7, 2).unwrap().score, engine::MATE - 5);
+
+        table.store(8, 3, transposition::Bound::Exact, -(engine::MATE - 6), None, 4);
+        assert_eq!(table.probe(8, 0).unwrap().score, -(engine::MATE - 2));
+    }
+
+    // a deeper entry from the current search isn't replaced by a shallower one for another position
+    #[test]
+    fn replacement() {
+        let table = transposition::TranspositionTable::new(0);
+        assert_eq!(table.capacity(), 1);
+        table.store(1, 6, transposition::Bound::Exact, 0, None, 0);
+        table.store(2, 2, transposition::Bound::Exact, 0, None, 0);
+        assert_eq!(table.probe(1, 0).is_some(), true);
+        assert_eq!(table.hashfull(), 1000);
+
+        // entries from an earlier search make way
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+        table.store(2, 2, transposition::Bound::Exact, 0, None, 0);
+        assert_eq!(table.probe(1, 0), None);
+        assert_eq!(table.probe(2, 0).is_some(), true);
+    }
+
+    // searching again with what the table learned finds the same result with fewer nodes
+    #[test]
+    fn search_reuses_table() {
+        let board = fen::parse_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+        ).unwrap();
+        let limits = engine::SearchLimits { depth: 4, ..Default::default() };
+        let mut engine = engine::Engine::with_hash_size(1);
+        let first = engine.search(&board, &limits);
+        let second = engine.search(&board, &limits);
+        assert_eq!(second.best_move, first.best_move);
+        assert_eq!(second.score, first.score);
+        assert_eq!(second.nodes < first.nodes, true);
+        assert_eq!(second.pv.is_empty(), false);
+
+        let mate = engine.search(
+            &fen::parse_fen("3r2k1/5ppp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap(),
+            &limits
+        );
+        assert_eq!(mate.score, engine::Score::Mate(2));
+    }
+}
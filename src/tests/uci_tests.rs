@@ -0,0 +1,261 @@
+#[cfg(test)]
+mod uci_tests {
+    use crate::engine;
+    use crate::fen;
+    use crate::game;
+    use crate::notation;
+    use crate::pieces;
+    use crate::uci;
+    use std::io::Write;
+    use std::sync::{ Arc, Mutex };
+
+    // output the test can still read once the engine has been handed it
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    fn run(commands: &str) -> Vec<String> {
+        let output = SharedOutput::default();
+        uci::uci_loop(commands.as_bytes(), output.clone());
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        return text.lines().map(|line| line.to_string()).collect();
+    }
+
+    fn bestmove(lines: &[String]) -> String {
+        let line = lines.iter().find(|line| line.starts_with("bestmove")).unwrap();
+        return line.split_whitespace().nth(1).unwrap().to_string();
+    }
+
+    #[test]
+    fn handshake() {
+        let lines = run("uci\nisready\nquit\n");
+        assert_eq!(lines[0].starts_with("id name cli-chess"), true);
+        assert_eq!(lines.iter().any(|line| line.starts_with("option name Hash type spin")), true);
+        assert_eq!(lines.iter().any(|line| line.starts_with("option name Threads type spin")), true);
+        assert_eq!(lines[lines.len() - 2], "uciok");
+        assert_eq!(lines[lines.len() - 1], "readyok");
+    }
+
+    // the moves are replayed, so the search starts from the position after them
+    #[test]
+    fn position_and_go() {
+        // after 1. f3 e5 2. g4 black mates with Qh4
+        let lines = run("position startpos moves f2f3 e7e5 g2g4\ngo depth 2\n");
+        assert_eq!(bestmove(&lines), "d8h4");
+
+        let info = lines.iter().rfind(|line| line.starts_with("info depth")).unwrap();
+        assert_eq!(info.contains(" score mate 1 "), true);
+        for field in [" nodes ", " nps ", " time ", " hashfull ", " pv d8h4"] {
+            assert_eq!(info.contains(field), true);
+        }
+    }
+
+    #[test]
+    fn position_from_fen() {
+        let lines = run("position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1\ngo depth 3\n");
+        assert_eq!(bestmove(&lines), "a1a8");
+    }
+
+    #[test]
+    fn invalid_move() {
+        // the position stops at the last legal move, and white is to move
+        let lines = run("position startpos moves e2e4 e2e4\ngo depth 1\n");
+        assert_eq!(lines.iter().any(|line| line.starts_with("info string invalid move e2e4")), true);
+        let mv = bestmove(&lines);
+        assert_eq!(mv.starts_with(|c: char| "abcdefgh".contains(c)), true);
+        assert_eq!(["7", "8"].contains(&&mv[1..2]), true);
+    }
+
+    #[test]
+    fn go_nodes() {
+        let lines = run("go nodes 2000\n");
+        let infos: Vec<&String> = lines.iter().filter(|line| line.starts_with("info depth")).collect();
+        assert_eq!(infos.is_empty(), false);
+        assert_eq!(bestmove(&lines).len(), 4);
+    }
+
+    #[test]
+    fn go_movetime() {
+        let start = std::time::Instant::now();
+        let lines = run("go movetime 200\n");
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(2), true);
+        assert_eq!(bestmove(&lines).len(), 4);
+    }
+
+    #[test]
+    fn go_clock() {
+        let start = std::time::Instant::now();
+        let lines = run("position startpos moves e2e4\ngo wtime 1000 btime 3000 winc 0 binc 100\n");
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(3), true);
+        assert_eq!(bestmove(&lines).len(), 4);
+    }
+
+    // an infinite search only sends its best move once told to stop, and so does the end of the input
+    #[test]
+    fn infinite_and_stop() {
+        let lines = run("go infinite\nisready\nstop\n");
+        assert_eq!(lines.iter().any(|line| line == "readyok"), true);
+        assert_eq!(lines.last().unwrap().starts_with("bestmove"), true);
+
+        let lines = run("go infinite\n");
+        assert_eq!(lines.last().unwrap().starts_with("bestmove"), true);
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let lines = run("position startpos moves f2f3 e7e5 g2g4 d8h4\ngo depth 3\n");
+        assert_eq!(bestmove(&lines), "0000");
+    }
+
+    #[test]
+    fn setoption() {
+        let lines = run(
+            "setoption name Hash value 1\nsetoption name Threads value 2\nsetoption name Ponder value true\ngo depth 2\n"
+        );
+        assert_eq!(lines[0], "info string unknown option Ponder");
+        assert_eq!(bestmove(&lines).len(), 4);
+    }
+
+    #[test]
+    fn setoption_without_name() {
+        let lines = run("setoption
+setoption value 5
+go depth 2
+");
+        assert_eq!(lines[0], "info string expected setoption name <name> [value <value>]");
+        assert_eq!(lines[1], "info string expected setoption name <name> [value <value>]");
+        assert_eq!(bestmove(&lines).len(), 4);
+    }
+
+    #[test]
+    fn parse_uci() {
+        let board = fen::parse_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        let castle = notation::parse_uci(&board, "e1g1").unwrap();
+        assert_eq!(castle.piece_type, pieces::PieceType::King);
+        assert_eq!((castle.dst_file, castle.dst_rank), (6, 0));
+
+        let promotion = notation::parse_uci(&board, "b7a8n").unwrap();
+        assert_eq!(promotion.piece_type, pieces::PieceType::Knight);
+        assert_eq!(notation::to_uci(&board, promotion), "b7a8n");
+
+        assert_eq!(notation::parse_uci(&board, "b7b8").is_err(), true);
+        assert_eq!(notation::parse_uci(&board, "b7b8k").is_err(), true);
+        assert_eq!(notation::parse_uci(&board, "e1e3").is_err(), true);
+        assert_eq!(notation::parse_uci(&board, "e1").is_err(), true);
+    }
+
+    // a stand-in engine that logs what it is sent and answers every go with the given shell commands
+    fn stand_in(name: &str, answer: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("cli-chess-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (script, log) = (dir.join("engine.sh"), dir.join("engine.log"));
+        let source = format!(
+            "while read -r command rest; do\n\
+             echo \"$command $rest\" >> '{log}'\n\
+             case $command in\n\
+             uci) echo 'id name Stand-in 1.0'; echo 'option name Hash type spin default 1 min 1 max 8'; echo uciok ;;\n\
+             isready) echo readyok ;;\n\
+             go) {answer} ;;\n\
+             quit) exit 0 ;;\n\
+             esac\n\
+             done\n",
+            log = log.display()
+        );
+        std::fs::write(&script, source).unwrap();
+        return (script, log);
+    }
+
+    fn commands(log: &std::path::Path) -> Vec<String> {
+        let text = std::fs::read_to_string(log).unwrap();
+        return text.lines().map(|line| line.trim().to_string()).collect();
+    }
+
+    #[test]
+    fn external_engine() {
+        let (script, log) = stand_in(
+            "external",
+            "echo 'info depth 7 seldepth 9 score cp 31 nodes 4500 nps 90000 hashfull 12 pv e7e5 g1f3 b8c6'; \
+             echo 'bestmove e7e5 ponder g1f3'"
+        );
+        let mut engine = uci::UciEngine::start("sh", &[script.to_str().unwrap()]).unwrap();
+        assert_eq!(engine.name, "Stand-in 1.0");
+        engine.set_option("Hash", "4").unwrap();
+
+        let mut game = game::Game::new();
+        game.play_notation("e4").unwrap();
+        let result = engine.search(&game, &engine::SearchLimits::default()).unwrap();
+        assert_eq!(result.best_move, Some(notation::parse_uci(game.get_board(), "e7e5").unwrap()));
+        assert_eq!(result.score, engine::Score::Centipawns(31));
+        assert_eq!((result.depth, result.nodes, result.hashfull), (7, 4500, 12));
+        assert_eq!(result.pv.len(), 3);
+        game.play_move(result.best_move.unwrap()).unwrap();
+
+        // the game up to the current move is sent for every search, so going back keeps the engine in step
+        game.play_notation("Nf3").unwrap();
+        game.back();
+        game.back();
+        let limits = engine::SearchLimits {
+            depth: engine::MAX_DEPTH,
+            time_left: Some(std::time::Duration::from_secs(60)),
+            increment: std::time::Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(engine.search(&game, &limits).is_ok(), true);
+        drop(engine);
+
+        let sent = commands(&log);
+        assert_eq!(sent[0], "uci");
+        assert_eq!(sent.contains(&"ucinewgame".to_string()), true);
+        assert_eq!(sent.contains(&"setoption name Hash value 4".to_string()), true);
+        assert_eq!(sent.iter().filter(|&command| command == "position startpos moves e2e4").count(), 2);
+        assert_eq!(sent.contains(&"go depth 4".to_string()), true);
+        assert_eq!(sent.contains(&"go wtime 60000 btime 60000 winc 1000 binc 1000".to_string()), true);
+        assert_eq!(sent.last().unwrap(), "quit");
+    }
+
+    #[test]
+    fn external_engine_from_fen() {
+        let (script, log) = stand_in("fen", "echo 'info depth 1 score mate 1 pv a1a8'; echo 'bestmove a1a8'");
+        let mut engine = uci::UciEngine::start("sh", &[script.to_str().unwrap()]).unwrap();
+
+        let position = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1";
+        let game = game::Game::from_board(fen::parse_fen(position).unwrap());
+        let limits = engine::SearchLimits { move_time: Some(std::time::Duration::from_millis(500)), ..Default::default() };
+        let result = engine.search(&game, &limits).unwrap();
+        assert_eq!(result.score, engine::Score::Mate(1));
+        assert_eq!(notation::to_uci(game.get_board(), result.best_move.unwrap()), "a1a8");
+        drop(engine);
+
+        let sent = commands(&log);
+        assert_eq!(sent.contains(&format!("position fen {position}")), true);
+        assert_eq!(sent.contains(&"go movetime 500 depth 4".to_string()), true);
+    }
+
+    // an illegal move or an engine that goes away is an error, not a hang
+    #[test]
+    fn external_engine_errors() {
+        let (script, _) = stand_in("illegal", "echo 'info string thinking'; echo 'bestmove e2e5'");
+        let mut engine = uci::UciEngine::start("sh", &[script.to_str().unwrap()]).unwrap();
+        let game = game::Game::new();
+        let result = engine.search(&game, &engine::SearchLimits::default());
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        let (script, _) = stand_in("exits", "exit 1");
+        let mut engine = uci::UciEngine::start("sh", &[script.to_str().unwrap()]).unwrap();
+        let result = engine.search(&game, &engine::SearchLimits::default());
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+
+        assert_eq!(uci::UciEngine::start("/nonexistent/engine", &[]).is_err(), true);
+    }
+}
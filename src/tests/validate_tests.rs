@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod validate_tests {
+    use crate::board;
+    use crate::fen;
+    use crate::game;
+    use crate::pieces;
+
+    fn fen_error(position: &str) -> Option<board::SetupError> {
+        return fen::parse_fen(position).err();
+    }
+
+    #[test]
+    fn validate_starting_position() {
+        let mut board: board::Board = board::Board::new();
+        board.reset_board();
+        assert_eq!(board.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_kings() {
+        assert_eq!(
+            fen_error("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Some(board::SetupError::MissingKing(game::Player::Black))
+        );
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+            Some(board::SetupError::ExtraKing(game::Player::White))
+        );
+
+        // kings placed by hand are tracked like any other
+        let mut board: board::Board = board::Board::new();
+        board.place_piece(game::Player::White, pieces::PieceType::King, 4, 0);
+        assert_eq!(board.validate(), Err(board::SetupError::MissingKing(game::Player::Black)));
+        board.place_piece(game::Player::Black, pieces::PieceType::King, 4, 7);
+        assert_eq!(board.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_piece_counts() {
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/PPPPPPPP/QQQQKQQQ w - - 0 1"),
+            Some(board::SetupError::TooManyPromotedPieces(game::Player::White))
+        );
+        assert_eq!(fen_error("4k3/8/8/8/8/8/8/QQQQKQQQ w - - 0 1"), None);
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/7P/PPPPPPPP/RNBQKBNR w KQ - 0 1"),
+            Some(board::SetupError::TooManyPieces(game::Player::White))
+        );
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/8/P3K3 w - - 0 1"),
+            Some(board::SetupError::PawnOnBackRank)
+        );
+    }
+
+    #[test]
+    fn validate_check() {
+        assert_eq!(fen_error("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), None);
+        assert_eq!(fen_error("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1"), None);
+
+        // white to move but black is already in check
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            Some(board::SetupError::OpponentInCheck)
+        );
+    }
+
+    #[test]
+    fn validate_castling_and_en_passant() {
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+            Some(board::SetupError::InvalidCastlingRights)
+        );
+        assert_eq!(
+            fen_error("r3k3/8/8/8/8/8/8/4K3 w k - 0 1"),
+            Some(board::SetupError::InvalidCastlingRights)
+        );
+        assert_eq!(
+            fen_error("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"),
+            Some(board::SetupError::InvalidEnPassant)
+        );
+        assert_eq!(
+            fen_error("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"),
+            Some(board::SetupError::InvalidEnPassant)
+        );
+        assert_eq!(fen_error("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"), None);
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod xboard_tests {
+    use crate::xboard;
+    use std::io::Write;
+    use std::sync::{ Arc, Mutex };
+
+    // output the test can still read once the engine has been handed it
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    fn run(commands: &str) -> Vec<String> {
+        let output = SharedOutput::default();
+        xboard::xboard_loop(commands.as_bytes(), output.clone());
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        return text.lines().map(|line| line.to_string()).collect();
+    }
+
+    fn engine_moves(lines: &[String]) -> Vec<String> {
+        return lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("move "))
+            .map(|mv| mv.to_string())
+            .collect();
+    }
+
+    #[test]
+    fn handshake() {
+        let lines = run("xboard\nprotover 2\naccepted setboard\nping 7\n");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].starts_with("feature myname=\"cli-chess"), true);
+        for feature in ["setboard=1", "usermove=1", "ping=1", "sigint=0", "memory=1", "smp=1"] {
+            assert_eq!(lines[0].contains(feature), true);
+        }
+        assert_eq!(lines[0].ends_with("done=1"), true);
+        assert_eq!(lines[1], "pong 7");
+    }
+
+    // after new the engine plays black, and answers each of white's moves
+    #[test]
+    fn new_game() {
+        let lines = run("xboard\nnew\nsd 2\nusermove e2e4\nping 1\n");
+        let moves = engine_moves(&lines);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].ends_with('6') || moves[0].ends_with('5'), true);
+        // the pong only comes once the engine has moved
+        assert_eq!(lines.last().unwrap(), "pong 1");
+    }
+
+    #[test]
+    fn force_and_go() {
+        let lines = run("new\nforce\nusermove e2e4\nusermove e7e5\nusermove g1f3\nsd 2\nping 1\ngo\n");
+        assert_eq!(lines[0], "pong 1");
+        let moves = engine_moves(&lines);
+        assert_eq!(moves.len(), 1);
+
+        // go makes the engine play the side to move from then on
+        let lines = run("new\nforce\nusermove e2e4\nsd 1\ngo\nping 1\nusermove d2d4\nping 2\n");
+        assert_eq!(engine_moves(&lines).len(), 2);
+    }
+
+    #[test]
+    fn setboard_and_result() {
+        let lines = run("new\nforce\nsetboard 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1\nsd 3\ngo\n");
+        assert_eq!(engine_moves(&lines), vec!["a1a8".to_string()]);
+        assert_eq!(lines.last().unwrap(), "1-0 {White mates}");
+
+        let lines = run("setboard 8/8/8/8/8/8/8/8 w - - 0 1\n");
+        assert_eq!(lines, vec!["tellusererror Illegal position".to_string()]);
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let lines = run("new\nforce\nusermove e2e5\nusermove e7e5\nusermove e2\n");
+        assert_eq!(
+            lines,
+            vec!["Illegal move: e2e5".to_string(), "Illegal move: e7e5".to_string(), "Illegal move: e2".to_string()]
+        );
+    }
+
+    #[test]
+    fn thinking_output() {
+        let lines = run("new\npost\nsd 3\nusermove e2e4\n");
+        let thinking: Vec<&String> = lines
+            .iter()
+            .filter(|line| !line.starts_with("move"))
+            .collect();
+        assert_eq!(thinking.len(), 3);
+        for (depth, line) in thinking.iter().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(fields[0], (depth + 1).to_string());
+            for field in &fields[1..4] {
+                assert_eq!(field.parse::<i64>().is_ok(), true);
+            }
+            assert_eq!(fields.len() > 4, true);
+        }
+
+        let lines = run("new\nsd 3\nusermove e2e4\n");
+        assert_eq!(lines.len(), 1);
+    }
+
+    // a time control makes the engine search as long as its clock allows rather than to a fixed depth
+    #[test]
+    fn time_controls() {
+        let start = std::time::Instant::now();
+        let lines = run("new\nst 0.2\nusermove e2e4\n");
+        assert_eq!(engine_moves(&lines).len(), 1);
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(2), true);
+
+        let start = std::time::Instant::now();
+        let lines = run("new\nlevel 40 0:30 0\ntime 500\notim 3000\nusermove e2e4\n");
+        assert_eq!(engine_moves(&lines).len(), 1);
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(3), true);
+
+        let lines = run("level 40 five 0\nst soon\nsd none\n");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines.iter().all(|line| line.starts_with("Error")), true);
+    }
+
+    // ? moves at once, while force abandons the search without moving
+    #[test]
+    fn interrupting() {
+        let start = std::time::Instant::now();
+        let lines = run("new\nst 30\nusermove e2e4\n?\n");
+        assert_eq!(engine_moves(&lines).len(), 1);
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(10), true);
+
+        let start = std::time::Instant::now();
+        let lines = run("new\nst 30\nusermove e2e4\nforce\nping 1\n");
+        assert_eq!(lines, vec!["pong 1".to_string()]);
+        assert_eq!(start.elapsed() < std::time::Duration::from_secs(10), true);
+    }
+
+    // the number of threads is kept to what UCI allows, however many cores the GUI offers
+    #[test]
+    fn cores_are_clamped() {
+        let lines = run("new\ncores 100000\nsd 1\nusermove e2e4\nping 1\n");
+        assert_eq!(engine_moves(&lines).len(), 1);
+        assert_eq!(lines.last().unwrap(), "pong 1");
+    }
+
+    #[test]
+    fn unknown_command() {
+        let lines = run("hint\n");
+        assert_eq!(lines, vec!["Error (unknown command): hint".to_string()]);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::board;
+use crate::engine;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::pieces;
+use std::sync::atomic::{ AtomicU64, AtomicU8, Ordering };
+
+// random keys for Zobrist hashing, generated at compile time so every run hashes the same
+// 0..768: a piece of each player and type on each square
+// 768: Black to move, 769..773: castling rights, 773..781: en passant file
+const KEY_COUNT: usize = 781;
+const SIDE_KEY: usize = 768;
+const CASTLING_KEYS: usize = 769;
+const EN_PASSANT_KEYS: usize = 773;
+
+const KEYS: [u64; KEY_COUNT] = generate_keys();
+
+// splitmix64, seeded with a fixed constant
+const fn generate_keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    return keys;
+}
+
+// a 64-bit key for the position: placement, side to move, castling rights and en passant
+// positions that transpose into each other get the same key regardless of the move counters
+pub fn hash_position(board: &board::Board) -> u64 {
+    let mut key = 0;
+    for (p, player) in [game::Player::White, game::Player::Black].into_iter().enumerate() {
+        for &(file, rank) in board.get_pieces(player) {
+            let piece_type = board.squares[board::convert_position_1d(file, rank)].get_type();
+            let square = (rank * 8 + file) as usize;
+            // piece types start at 1, after Empty
+            key ^= KEYS[(p * 6 + piece_type as usize - 1) * 64 + square];
+        }
+    }
+
+    let player = match board.get_state() {
+        game::GameState::Playing(p) => *p,
+        _ => {
+            if board.get_turn() % 2 == 0 { game::Player::White } else { game::Player::Black }
+        }
+    };
+    if player == game::Player::Black {
+        key ^= KEYS[SIDE_KEY];
+    }
+
+    let rights = [
+        (game::Player::White, 0, 7),
+        (game::Player::White, 0, 0),
+        (game::Player::Black, 7, 7),
+        (game::Player::Black, 7, 0),
+    ];
+    for (i, (p, rank, rook_file)) in rights.into_iter().enumerate() {
+        if fen::can_castle(board, p, rank, rook_file) {
+            key ^= KEYS[CASTLING_KEYS + i];
+        }
+    }
+
+    if let Some((file, _)) = fen::en_passant_target(board, player) {
+        key ^= KEYS[EN_PASSANT_KEYS + file as usize];
+    }
+    return key;
+}
+
+// how the stored score relates to the real score of the position
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Bound {
+    Exact,
+    // the search failed high, the real score is at least this
+    Lower,
+    // the search failed low, the real score is at most this
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TableEntry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    // mate scores are stored relative to this position, not the root of the search
+    pub score: i32,
+    pub best_move: Option<moves::PieceMove>,
+    // the search that stored the entry, so entries from old searches get replaced first
+    pub age: u8,
+}
+
+// a fixed-size hash table of search results, indexed by the position's key
+// threads read and write it without locking: each entry is packed into one word and stored next to
+// its key XORed with that word, so an entry torn by two threads writing at once no longer matches
+// its key and is simply ignored
+pub struct TranspositionTable {
+    // two words per entry: the key XOR the data, then the data
+    entries: Vec<AtomicU64>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    // a table using about the given number of megabytes, with room for at least one entry
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let size = (megabytes * 1024 * 1024 / ENTRY_SIZE).max(1);
+        return TranspositionTable {
+            entries: (0..size * 2).map(|_| AtomicU64::new(0)).collect(),
+            age: AtomicU8::new(0),
+        };
+    }
+
+    pub fn capacity(&self) -> usize {
+        return self.entries.len() / 2;
+    }
+
+    pub fn clear(&self) {
+        self.entries.iter().for_each(|word| word.store(0, Ordering::Relaxed));
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    // entries stored from now on belong to a new search
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // per mille of the sampled entries filled in by the current search
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.capacity().min(1000);
+        let age = self.age.load(Ordering::Relaxed);
+        let used = (0..sample)
+            .filter(|&i| matches!(self.load(i), Some(entry) if entry.age == age))
+            .count();
+        return (used * 1000 / sample) as u32;
+    }
+
+    // the entry for the position, with mate scores made relative to the root again
+    pub fn probe(&self, key: u64, ply: i32) -> Option<TableEntry> {
+        return match self.load(self.index(key)) {
+            Some(entry) if entry.key == key => {
+                Some(TableEntry { score: score_from_table(entry.score, ply), ..entry })
+            }
+            _ => None,
+        };
+    }
+
+    // keep the entry unless the slot holds a deeper result for another position from this search
+    pub fn store(
+        &self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<moves::PieceMove>,
+        ply: i32
+    ) {
+        let index = self.index(key);
+        let age = self.age.load(Ordering::Relaxed);
+        let old = self.load(index);
+        if let Some(old) = old {
+            if old.key != key && old.age == age && old.depth > depth {
+                return;
+            }
+        }
+        // a search that didn't find a move shouldn't lose the one stored before
+        let best_move = best_move.or(match old {
+            Some(old) if old.key == key => old.best_move,
+            _ => None,
+        });
+        let data = pack(&TableEntry {
+            key,
+            depth,
+            bound,
+            score: score_to_table(score, ply),
+            best_move,
+            age,
+        });
+        self.entries[index * 2].store(key ^ data, Ordering::Relaxed);
+        self.entries[index * 2 + 1].store(data, Ordering::Relaxed);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        return (key % (self.capacity() as u64)) as usize;
+    }
+
+    fn load(&self, index: usize) -> Option<TableEntry> {
+        let check = self.entries[index * 2].load(Ordering::Relaxed);
+        let data = self.entries[index * 2 + 1].load(Ordering::Relaxed);
+        return unpack(check ^ data, data);
+    }
+}
+
+const ENTRY_SIZE: usize = 2 * std::mem::size_of::<u64>();
+const SCORE_BITS: u32 = 20;
+const SCORE_OFFSET: i32 = 1 << (SCORE_BITS - 1);
+
+// bits 0..20: score, 20..28: depth, 28..30: bound, 30..38: age,
+// 38..54: best move (a flag, then from square, to square and piece type)
+fn pack(entry: &TableEntry) -> u64 {
+    let bound: u64 = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let best_move = match entry.best_move {
+        Some(mv) => {
+            let src = (mv.src_rank as u64) * 8 + (mv.src_file as u64);
+            let dst = (mv.dst_rank as u64) * 8 + (mv.dst_file as u64);
+            1 | (src << 1) | (dst << 7) | ((mv.piece_type as u64) << 13)
+        }
+        None => 0,
+    };
+    return ((entry.score + SCORE_OFFSET) as u64) |
+        ((entry.depth.min(255) as u64) << 20) |
+        (bound << 28) |
+        ((entry.age as u64) << 30) |
+        (best_move << 38);
+}
+
+// an empty slot has no bound
+fn unpack(key: u64, data: u64) -> Option<TableEntry> {
+    let bound = match (data >> 28) & 3 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => {
+            return None;
+        }
+    };
+    let code = (data >> 38) & 0xffff;
+    let best_move = if code & 1 == 0 {
+        None
+    } else {
+        let (src, dst) = (((code >> 1) & 63) as i8, ((code >> 7) & 63) as i8);
+        let piece_type = match (code >> 13) & 7 {
+            1 => pieces::PieceType::King,
+            2 => pieces::PieceType::Queen,
+            3 => pieces::PieceType::Rook,
+            4 => pieces::PieceType::Bishop,
+            5 => pieces::PieceType::Knight,
+            _ => pieces::PieceType::Pawn,
+        };
+        Some(moves::PieceMove::new(piece_type, src % 8, src / 8, dst % 8, dst / 8))
+    };
+    return Some(TableEntry {
+        key,
+        depth: ((data >> 20) & 255) as u32,
+        bound,
+        score: ((data & ((1 << SCORE_BITS) - 1)) as i32) - SCORE_OFFSET,
+        best_move,
+        age: ((data >> 30) & 255) as u8,
+    });
+}
+
+// a mate found n plies below this position is n plies away from it, wherever the search started
+fn score_to_table(score: i32, ply: i32) -> i32 {
+    if score > engine::MATE_BOUND {
+        return score + ply;
+    }
+    if score < -engine::MATE_BOUND {
+        return score - ply;
+    }
+    return score;
+}
+
+fn score_from_table(score: i32, ply: i32) -> i32 {
+    if score > engine::MATE_BOUND {
+        return score - ply;
+    }
+    if score < -engine::MATE_BOUND {
+        return score + ply;
+    }
+    return score;
+}
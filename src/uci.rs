@@ -0,0 +1,532 @@
+use crate::board;
+use crate::engine;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::notation;
+use std::io::{ BufRead, Write };
+use std::process::{ Child, ChildStdin, ChildStdout, Command, Stdio };
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::thread::JoinHandle;
+use std::time::{ Duration, Instant };
+
+pub(crate) const MAX_HASH_SIZE: usize = 4096;
+pub(crate) const MAX_THREADS: usize = 256;
+
+// the engine and the position a GUI has set up
+// searches run on their own thread so that stop and isready are answered while they run
+struct UciState<W: Write + Send + 'static> {
+    output: Arc<Mutex<W>>,
+    board: board::Board,
+    // the engine is handed to the search thread and comes back when the search is over
+    engine: Option<engine::Engine>,
+    search: Option<JoinHandle<engine::Engine>>,
+    // the running engine's stop signal
+    engine_stop: Arc<AtomicBool>,
+    // an infinite search waits for stop before sending its best move
+    infinite: bool,
+    stopped: Arc<AtomicBool>,
+}
+
+// speak the Universal Chess Interface until quit or the end of the input
+// the end of the input lets a running search finish, unless it would never finish on its own
+pub fn uci_loop<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let engine = engine::Engine::new();
+    let mut state = UciState {
+        output: Arc::new(Mutex::new(output)),
+        board: starting_board(),
+        engine_stop: engine.stop_signal(),
+        engine: Some(engine),
+        search: None,
+        infinite: false,
+        stopped: Arc::new(AtomicBool::new(false)),
+    };
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => {
+                break;
+            }
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("uci") => {
+                send(&state.output, &format!("id name cli-chess {}", env!("CARGO_PKG_VERSION")));
+                send(&state.output, "id author the cli-chess authors");
+                send(
+                    &state.output,
+                    &format!(
+                        "option name Hash type spin default {} min 1 max {MAX_HASH_SIZE}",
+                        engine::DEFAULT_HASH_SIZE
+                    )
+                );
+                send(&state.output, &format!("option name Threads type spin default 1 min 1 max {MAX_THREADS}"));
+                send(&state.output, "uciok");
+            }
+            Some("isready") => send(&state.output, "readyok"),
+            Some("ucinewgame") => {
+                state.finish_search(true);
+                state.board = starting_board();
+                state.engine.as_mut().unwrap().clear_hash();
+            }
+            Some("setoption") => {
+                state.finish_search(true);
+                state.set_option(&tokens[1..]);
+            }
+            Some("position") => {
+                state.finish_search(true);
+                state.set_position(&tokens[1..]);
+            }
+            Some("go") => {
+                state.finish_search(true);
+                state.go(&tokens[1..]);
+            }
+            Some("stop") => state.finish_search(true),
+            Some("quit") => {
+                state.finish_search(true);
+                return;
+            }
+            // unknown commands are ignored, as the protocol asks
+            _ => {}
+        }
+    }
+    let infinite = state.infinite;
+    state.finish_search(infinite);
+}
+
+impl<W: Write + Send + 'static> UciState<W> {
+    // wait for the running search, if any, stopping it first if asked to
+    fn finish_search(&mut self, stop: bool) {
+        let search = match self.search.take() {
+            Some(search) => search,
+            None => {
+                return;
+            }
+        };
+        if stop {
+            self.stopped.store(true, Ordering::Relaxed);
+            // keep signalling until the search is over, in case it only just started and cleared the signal
+            while !search.is_finished() {
+                self.engine_stop.store(true, Ordering::Relaxed);
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        self.engine = Some(search.join().unwrap());
+    }
+
+    // setoption name <name> value <value>
+    fn set_option(&mut self, tokens: &[&str]) {
+        let value_at = tokens.iter().position(|&token| token == "value");
+        let name_end = value_at.unwrap_or(tokens.len());
+        if tokens.first() != Some(&"name") || name_end < 2 {
+            send(&self.output, "info string expected setoption name <name> [value <value>]");
+            return;
+        }
+        let name = tokens[1..name_end].join(" ");
+        let value = value_at.and_then(|i| tokens.get(i + 1)).and_then(|value| value.parse::<usize>().ok());
+        let engine = self.engine.as_mut().unwrap();
+        match (name.to_ascii_lowercase().as_str(), value) {
+            ("hash", Some(megabytes)) => engine.set_hash_size(megabytes.clamp(1, MAX_HASH_SIZE)),
+            ("threads", Some(threads)) => engine.set_threads(threads.clamp(1, MAX_THREADS)),
+            _ => send(&self.output, &format!("info string unknown option {name}")),
+        }
+    }
+
+    // position startpos | fen <FEN> [moves <move>...]
+    // an invalid position or move leaves the position as far as it could be set up
+    fn set_position(&mut self, tokens: &[&str]) {
+        let moves_at = tokens.iter().position(|&token| token == "moves").unwrap_or(tokens.len());
+        let board = match tokens.first().copied() {
+            Some("startpos") => Ok(starting_board()),
+            Some("fen") => fen::parse_fen(&tokens[1..moves_at].join(" ")),
+            _ => {
+                send(&self.output, "info string expected startpos or fen");
+                return;
+            }
+        };
+        self.board = match board {
+            Ok(board) => board,
+            Err(e) => {
+                send(&self.output, &format!("info string invalid position: {e}"));
+                return;
+            }
+        };
+
+        for uci in tokens.iter().skip(moves_at + 1) {
+            let player = match self.board.get_state() {
+                game::GameState::Playing(p) => *p,
+                _ => {
+                    send(&self.output, &format!("info string the game is over before {uci}"));
+                    return;
+                }
+            };
+            let result = notation::parse_uci(&self.board, uci).and_then(|mv| self.board.apply_move(player, mv));
+            if let Err(e) = result {
+                send(&self.output, &format!("info string invalid move {uci}: {e}"));
+                return;
+            }
+        }
+    }
+
+    // go [depth <plies>] [nodes <count>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>]
+    //    [binc <ms>] [movestogo <moves>] [infinite]
+    fn go(&mut self, tokens: &[&str]) {
+        let player = match self.board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => game::Player::White,
+        };
+        let mut limits = engine::SearchLimits { depth: engine::MAX_DEPTH, ..Default::default() };
+        self.infinite = false;
+        let mut i = 0;
+        while i < tokens.len() {
+            let value = tokens.get(i + 1).and_then(|value| value.parse::<i64>().ok());
+            let milliseconds = value.map(|ms| Duration::from_millis(ms.max(0) as u64));
+            match (tokens[i], player) {
+                ("infinite", _) => {
+                    self.infinite = true;
+                    i += 1;
+                    continue;
+                }
+                ("depth", _) => {
+                    limits.depth = value.map_or(engine::MAX_DEPTH, |depth| depth.clamp(1, engine::MAX_DEPTH as i64) as u32);
+                }
+                ("nodes", _) => {
+                    limits.nodes = value.map(|nodes| nodes.max(1) as u64);
+                }
+                ("movetime", _) => {
+                    limits.move_time = milliseconds;
+                }
+                ("movestogo", _) => {
+                    limits.moves_to_go = value.map(|moves| moves.max(1) as u32);
+                }
+                ("wtime", game::Player::White) | ("btime", game::Player::Black) => {
+                    limits.time_left = milliseconds;
+                }
+                ("winc", game::Player::White) | ("binc", game::Player::Black) => {
+                    limits.increment = milliseconds.unwrap_or(Duration::ZERO);
+                }
+                // the other side's clock and anything unknown
+                _ => {}
+            }
+            i += 2;
+        }
+
+        let mut engine = self.engine.take().unwrap();
+        self.engine_stop = engine.stop_signal();
+        self.stopped.store(false, Ordering::Relaxed);
+        let infinite = self.infinite;
+        let stopped = Arc::clone(&self.stopped);
+        let output = Arc::clone(&self.output);
+        let board = self.board.clone();
+        self.search = Some(
+            std::thread::spawn(move || {
+                let result = engine.search_with(&board, &limits, |iteration| {
+                    send(&output, &info(&board, iteration));
+                });
+                // the GUI decides when an infinite search is over
+                while infinite && !stopped.load(Ordering::Relaxed) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+
+                let line = uci_line(&board, &result.pv);
+                let bestmove = match (result.best_move, line.get(1)) {
+                    (Some(mv), Some(ponder)) => format!("bestmove {} ponder {ponder}", notation::to_uci(&board, mv)),
+                    (Some(mv), None) => format!("bestmove {}", notation::to_uci(&board, mv)),
+                    // no legal moves
+                    (None, _) => "bestmove 0000".to_string(),
+                };
+                send(&output, &bestmove);
+                return engine;
+            })
+        );
+    }
+}
+
+pub(crate) fn starting_board() -> board::Board {
+    let mut board = board::Board::new();
+    board.reset_board();
+    return board;
+}
+
+// one line to the GUI, flushed right away
+pub(crate) fn send<W: Write>(output: &Mutex<W>, line: &str) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{line}");
+    let _ = output.flush();
+}
+
+// info depth <plies> score cp <centipawns> | mate <moves> nodes <count> nps <count> time <ms>
+//      hashfull <per mille> pv <move>...
+fn info(board: &board::Board, result: &engine::SearchResult) -> String {
+    let score = match result.score {
+        engine::Score::Centipawns(cp) => format!("cp {cp}"),
+        engine::Score::Mate(moves) => format!("mate {moves}"),
+    };
+    let milliseconds = result.elapsed.as_millis().max(1);
+    let nps = (result.nodes as u128) * 1000 / milliseconds;
+    return format!(
+        "info depth {} score {score} nodes {} nps {nps} time {} hashfull {} pv {}",
+        result.depth,
+        result.nodes,
+        result.elapsed.as_millis(),
+        result.hashfull,
+        uci_line(board, &result.pv).join(" ")
+    );
+}
+
+// the moves of a line in coordinate notation, each written for the position it is played in
+fn uci_line(board: &board::Board, line: &[moves::PieceMove]) -> Vec<String> {
+    let mut board = board.clone();
+    let mut moves = Vec::new();
+    for &mv in line {
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => {
+                break;
+            }
+        };
+        moves.push(notation::to_uci(&board, mv));
+        if board.apply_move(player, mv).is_err() {
+            break;
+        }
+    }
+    return moves;
+}
+
+// an external engine run as a subprocess and spoken to over UCI, to play against instead of our own
+pub struct UciEngine {
+    // as the engine introduces itself, or the program it was started as
+    pub name: String,
+    process: Child,
+    input: ChildStdin,
+    output: std::io::BufReader<ChildStdout>,
+}
+
+impl UciEngine {
+    // start the engine and wait until it is ready for a new game
+    pub fn start(program: &str, args: &[&str]) -> std::io::Result<UciEngine> {
+        let mut process = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let input = process.stdin.take().unwrap();
+        let output = std::io::BufReader::new(process.stdout.take().unwrap());
+        let mut engine = UciEngine { name: program.to_string(), process, input, output };
+
+        engine.send("uci")?;
+        loop {
+            let line = engine.receive()?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.send("ucinewgame")?;
+        engine.wait_until_ready()?;
+        return Ok(engine);
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> std::io::Result<()> {
+        self.send(&format!("setoption name {name} value {value}"))?;
+        return self.wait_until_ready();
+    }
+
+    // have the engine search the current position of a game, up to the given limits
+    // the engine is sent the whole game each time, so it stays in sync however the game was navigated
+    pub fn search(&mut self, game: &game::Game, limits: &engine::SearchLimits) -> std::io::Result<engine::SearchResult> {
+        let start = game.get_node(0).get_board();
+        let mut position = match fen::to_fen(start) {
+            start_fen if start_fen == fen::STARTING_FEN => "position startpos".to_string(),
+            start_fen => format!("position fen {start_fen}"),
+        };
+        let played: Vec<moves::PieceMove> = game
+            .get_moves()
+            .iter()
+            .map(|game_move| game_move.mv)
+            .collect();
+        if !played.is_empty() {
+            position += &format!(" moves {}", uci_line(start, &played).join(" "));
+        }
+        self.send(&position)?;
+
+        // the computer's clock stands in for both, since only the side to move's matters to the engine
+        let mut go = "go".to_string();
+        if let Some(time_left) = limits.time_left {
+            let (time, increment) = (time_left.as_millis(), limits.increment.as_millis());
+            go += &format!(" wtime {time} btime {time} winc {increment} binc {increment}");
+            if let Some(moves_to_go) = limits.moves_to_go {
+                go += &format!(" movestogo {moves_to_go}");
+            }
+        }
+        if let Some(move_time) = limits.move_time {
+            go += &format!(" movetime {}", move_time.as_millis());
+        }
+        if let Some(nodes) = limits.nodes {
+            go += &format!(" nodes {nodes}");
+        }
+        // a search with no limit at all would never end
+        if limits.depth < engine::MAX_DEPTH || go == "go" {
+            go += &format!(" depth {}", limits.depth);
+        }
+        let started = Instant::now();
+        self.send(&go)?;
+
+        let board = game.get_board();
+        let mut result = engine::SearchResult {
+            best_move: None,
+            score: engine::Score::Centipawns(0),
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            hashfull: 0,
+        };
+        loop {
+            let line = self.receive()?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.first().copied() {
+                Some("info") => read_info(board, &tokens[1..], &mut result),
+                Some("bestmove") => {
+                    result.best_move = match tokens.get(1).copied() {
+                        Some("0000") | Some("(none)") | None => None,
+                        Some(uci) =>
+                            match notation::parse_uci(board, uci) {
+                                Ok(mv) => Some(mv),
+                                Err(_) => {
+                                    return Err(
+                                        std::io::Error::new(
+                                            std::io::ErrorKind::InvalidData,
+                                            format!("{} played an illegal move: {uci}", self.name)
+                                        )
+                                    );
+                                }
+                            }
+                    };
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        // the line always starts with the move played, even if the engine didn't report one
+        result.pv = match result.best_move {
+            Some(mv) if result.pv.first() == Some(&mv) => result.pv,
+            Some(mv) => vec![mv],
+            None => Vec::new(),
+        };
+        result.elapsed = started.elapsed();
+        return Ok(result);
+    }
+
+    fn send(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.input, "{line}")?;
+        return self.input.flush();
+    }
+
+    fn receive(&mut self) -> std::io::Result<String> {
+        let mut line = String::new();
+        if self.output.read_line(&mut line)? == 0 {
+            return Err(
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, format!("{} has exited", self.name))
+            );
+        }
+        return Ok(line);
+    }
+
+    fn wait_until_ready(&mut self) -> std::io::Result<()> {
+        self.send("isready")?;
+        while self.receive()?.trim() != "readyok" {}
+        return Ok(());
+    }
+}
+
+impl Drop for UciEngine {
+    // ask the engine to quit, and make sure it has
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        for _ in 0..100 {
+            if let Ok(Some(_)) = self.process.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+// the parts of an engine's info line that a search result keeps
+// info depth <plies> [seldepth <plies>] score cp <centipawns> | mate <moves> nodes <count> ... pv <move>...
+fn read_info(board: &board::Board, tokens: &[&str], result: &mut engine::SearchResult) {
+    let value = |i: usize| tokens.get(i + 1).and_then(|value| value.parse::<i64>().ok());
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            // free text to the end of the line
+            "string" => {
+                return;
+            }
+            "depth" => {
+                result.depth = value(i).unwrap_or(0) as u32;
+            }
+            "nodes" => {
+                result.nodes = value(i).unwrap_or(0) as u64;
+            }
+            "hashfull" => {
+                result.hashfull = value(i).unwrap_or(0) as u32;
+            }
+            "score" => {
+                let score = tokens.get(i + 2).and_then(|value| value.parse::<i32>().ok());
+                match (tokens.get(i + 1).copied(), score) {
+                    (Some("cp"), Some(cp)) => {
+                        result.score = engine::Score::Centipawns(cp);
+                    }
+                    (Some("mate"), Some(moves)) => {
+                        result.score = engine::Score::Mate(moves);
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            "pv" => {
+                result.pv = parse_line(board, &tokens[i + 1..]);
+                return;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+}
+
+// the moves of a line given in coordinate notation, as far as they are legal
+fn parse_line(board: &board::Board, line: &[&str]) -> Vec<moves::PieceMove> {
+    let mut board = board.clone();
+    let mut moves = Vec::new();
+    for uci in line {
+        let player = match board.get_state() {
+            game::GameState::Playing(p) => *p,
+            _ => {
+                break;
+            }
+        };
+        let mv = match notation::parse_uci(&board, uci) {
+            Ok(mv) => mv,
+            Err(_) => {
+                break;
+            }
+        };
+        moves.push(mv);
+        if board.apply_move(player, mv).is_err() {
+            break;
+        }
+    }
+    return moves;
+}
@@ -0,0 +1,140 @@
+use crate::board;
+use crate::game;
+use crate::moves;
+use crate::notation;
+use crate::pgn;
+use std::io::BufRead;
+
+// something wrong with a game found while replaying it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    // the PGN itself could not be read
+    Syntax(pgn::PgnError),
+    // the FEN tag does not describe a legal position
+    InvalidSetup(board::SetupError),
+    // a move in the main line could not be played
+    IllegalMove {
+        move_number: i32,
+        player: game::Player,
+        san: String,
+        error: moves::MoveError,
+    },
+    // the result in the tags or movetext disagrees with how the game ended
+    ResultMismatch {
+        tag: String,
+        expected: String,
+    },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValidationIssue::Syntax(e) => write!(f, "{e}"),
+            ValidationIssue::InvalidSetup(e) => write!(f, "Invalid FEN tag: {e}"),
+            ValidationIssue::IllegalMove { move_number, player, san, error } => {
+                let dots = match player {
+                    game::Player::White => ".",
+                    game::Player::Black => "...",
+                };
+                write!(f, "move {move_number}{dots} {san}: {error}")
+            }
+            ValidationIssue::ResultMismatch { tag, expected } =>
+                write!(f, "Result is {tag} but the game ended {expected}"),
+        }
+    }
+}
+
+// a problem along with the (1-based) number of the game it was found in
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    pub game: usize,
+    pub issue: ValidationIssue,
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Game {}, {}", self.game, self.issue)
+    }
+}
+
+// replay the main line of a game, reporting the first illegal move and any result mismatch
+pub fn validate_game(pgn_game: &pgn::PgnGame) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let mut board = match pgn::starting_board(pgn_game.get_tag("FEN")) {
+        Ok(board) => board,
+        Err(e) => {
+            issues.push(ValidationIssue::InvalidSetup(e));
+            return issues;
+        }
+    };
+
+    for san in pgn_game.main_line() {
+        if let Err(error) = play_legal_move(&mut board, san) {
+            // later moves would only report the same mistake again
+            issues.push(pgn::illegal_move(&board, san, error));
+            return issues;
+        }
+    }
+
+    // the movetext result must agree with the tag
+    let tag = pgn_game.get_tag("Result").unwrap_or("*");
+    if tag != pgn_game.result {
+        issues.push(ValidationIssue::ResultMismatch {
+            tag: tag.to_string(),
+            expected: pgn_game.result.clone(),
+        });
+    }
+
+    // a game that ended on the board (mate or stalemate) must have that result
+    // any result is allowed otherwise, as games can end by resignation, agreement or time
+    let expected = pgn::result_token(board.get_state());
+    if expected != "*" && tag != expected {
+        issues.push(ValidationIssue::ResultMismatch {
+            tag: tag.to_string(),
+            expected: expected.to_string(),
+        });
+    }
+    return issues;
+}
+
+// play a move only if the rules allow it, however leniently its notation was read
+// a pawn reaching the last rank without saying what it becomes is a bad promotion
+fn play_legal_move(board: &mut board::Board, san: &str) -> Result<(), moves::MoveError> {
+    let player = match board.get_state() {
+        game::GameState::Playing(p) => *p,
+        _ => return Err(moves::MoveError::InvalidMove),
+    };
+    let mv = notation::parse_notation(board, &player, san)?;
+    let legal = board.legal_moves();
+    if !legal.contains(&mv) {
+        let same_squares = legal
+            .iter()
+            .any(|m| {
+                m.src_file == mv.src_file &&
+                    m.src_rank == mv.src_rank &&
+                    m.dst_file == mv.dst_file &&
+                    m.dst_rank == mv.dst_rank
+            });
+        return Err(if same_squares { moves::MoveError::InvalidPromotion } else { moves::MoveError::InvalidMove });
+    }
+    board.apply_move(player, mv)?;
+    return Ok(());
+}
+
+// validate every game in a PGN source, carrying on past broken games
+pub fn validate_pgn<R: BufRead>(reader: R) -> Vec<ValidationReport> {
+    let mut reports = Vec::new();
+    let mut games = pgn::PgnReader::new(reader);
+    while let Some(result) = games.next() {
+        let number = games.game_number();
+        let issues = match result {
+            Ok(pgn_game) => validate_game(&pgn_game),
+            Err(e) => vec![ValidationIssue::Syntax(e)],
+        };
+        for issue in issues {
+            reports.push(ValidationReport { game: number, issue });
+        }
+    }
+    return reports;
+}
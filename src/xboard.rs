@@ -0,0 +1,362 @@
+use crate::board;
+use crate::engine;
+use crate::fen;
+use crate::game;
+use crate::moves;
+use crate::notation;
+use crate::uci::{ MAX_HASH_SIZE, MAX_THREADS, send, starting_board };
+use std::io::{ BufRead, Write };
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// how a running search is brought to an end before the next command
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Finish {
+    // let it run until it moves
+    Wait,
+    // move now with the best move found so far
+    Stop,
+    // stop without moving, as the game has gone on without the engine
+    Abandon,
+}
+
+// the game as the GUI has set it up, and what the engine has been told to play
+// as with UCI, searches run on their own thread so that ? can interrupt them
+struct XboardState<W: Write + Send + 'static> {
+    output: Arc<Mutex<W>>,
+    board: board::Board,
+    // the side the engine plays, or None in force mode
+    engine_side: Option<game::Player>,
+    // the engine is handed to the search thread and comes back, along with the move it sent, when it is over
+    engine: Option<engine::Engine>,
+    search: Option<JoinHandle<(engine::Engine, Option<moves::PieceMove>)>>,
+    engine_stop: Arc<AtomicBool>,
+    abandoned: Arc<AtomicBool>,
+    // send thinking output while searching
+    post: bool,
+    // set by sd, st, level and time
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    moves_per_session: u32,
+    increment: Duration,
+    time_left: Option<Duration>,
+}
+
+// speak the Chess Engine Communication Protocol (as used by XBoard and WinBoard) until quit or the end of the input
+pub fn xboard_loop<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let engine = engine::Engine::new();
+    let mut state = XboardState {
+        output: Arc::new(Mutex::new(output)),
+        board: starting_board(),
+        engine_side: Some(game::Player::Black),
+        engine_stop: engine.stop_signal(),
+        engine: Some(engine),
+        search: None,
+        abandoned: Arc::new(AtomicBool::new(false)),
+        post: false,
+        depth: None,
+        move_time: None,
+        moves_per_session: 0,
+        increment: Duration::ZERO,
+        time_left: None,
+    };
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => {
+                break;
+            }
+        };
+        let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let argument = argument.trim();
+        let finish = match command {
+            "?" => Finish::Stop,
+            "new" | "force" | "result" | "setboard" | "quit" => Finish::Abandon,
+            _ => Finish::Wait,
+        };
+        state.finish_search(finish);
+
+        match command {
+            "protover" => {
+                send(
+                    &state.output,
+                    &format!(
+                        "feature myname=\"cli-chess {}\" setboard=1 usermove=1 ping=1 time=1 colors=0 sigint=0 sigterm=0 san=0 analyze=0 memory=1 smp=1 done=1",
+                        env!("CARGO_PKG_VERSION")
+                    )
+                );
+            }
+            "new" => {
+                state.board = starting_board();
+                state.engine_side = Some(game::Player::Black);
+                state.depth = None;
+                state.engine.as_mut().unwrap().clear_hash();
+            }
+            "force" | "result" => {
+                state.engine_side = None;
+            }
+            "go" => {
+                state.engine_side = side_to_move(&state.board);
+                state.think();
+            }
+            "usermove" => {
+                let player = side_to_move(&state.board);
+                let result = match player {
+                    Some(player) =>
+                        notation::parse_uci(&state.board, argument).and_then(|mv|
+                            state.board.apply_move(player, mv)
+                        ),
+                    None => Err(moves::MoveError::InvalidMove),
+                };
+                match result {
+                    Ok(_) => state.think(),
+                    Err(_) => send(&state.output, &format!("Illegal move: {argument}")),
+                }
+            }
+            "setboard" => {
+                match fen::parse_fen(argument) {
+                    Ok(board) => {
+                        state.board = board;
+                    }
+                    Err(_) => send(&state.output, "tellusererror Illegal position"),
+                }
+            }
+            "level" => {
+                if !state.set_level(argument) {
+                    send(&state.output, &format!("Error (invalid time control): {line}"));
+                }
+            }
+            "st" => {
+                match parse_seconds(argument) {
+                    Some(seconds) => {
+                        state.move_time = Some(seconds);
+                    }
+                    None => send(&state.output, &format!("Error (invalid time): {line}")),
+                }
+            }
+            "sd" => {
+                match argument.parse::<u32>() {
+                    Ok(depth) if depth > 0 => {
+                        state.depth = Some(depth.min(engine::MAX_DEPTH));
+                    }
+                    _ => send(&state.output, &format!("Error (invalid depth): {line}")),
+                }
+            }
+            // the engine's clock in centiseconds; the opponent's (otim) makes no difference to it
+            "time" => {
+                match argument.parse::<u64>() {
+                    Ok(centiseconds) => {
+                        state.time_left = Some(Duration::from_millis(centiseconds * 10));
+                    }
+                    Err(_) => send(&state.output, &format!("Error (invalid time): {line}")),
+                }
+            }
+            "ping" => send(&state.output, &format!("pong {argument}")),
+            "post" => {
+                state.post = true;
+            }
+            "nopost" => {
+                state.post = false;
+            }
+            "memory" => {
+                match argument.parse::<usize>() {
+                    Ok(megabytes) => {
+                        state.engine.as_mut().unwrap().set_hash_size(megabytes.clamp(1, MAX_HASH_SIZE));
+                    }
+                    Err(_) => send(&state.output, &format!("Error (invalid memory): {line}")),
+                }
+            }
+            "cores" => {
+                match argument.parse::<usize>() {
+                    Ok(cores) => state.engine.as_mut().unwrap().set_threads(cores.clamp(1, MAX_THREADS)),
+                    Err(_) => send(&state.output, &format!("Error (invalid cores): {line}")),
+                }
+            }
+            "quit" => {
+                return;
+            }
+            // accepted as they are, or of no use to this engine
+            "xboard" | "accepted" | "rejected" | "?" | "otim" | "easy" | "hard" | "random" | "computer" | "name" |
+            "rating" | "ics" => {}
+            _ => send(&state.output, &format!("Error (unknown command): {command}")),
+        }
+    }
+    state.finish_search(Finish::Wait);
+}
+
+impl<W: Write + Send + 'static> XboardState<W> {
+    // bring the running search, if any, to an end and play the move it sent
+    fn finish_search(&mut self, finish: Finish) {
+        let search = match self.search.take() {
+            Some(search) => search,
+            None => {
+                return;
+            }
+        };
+        if finish == Finish::Abandon {
+            // hold the output, so the search can't be sending its move at the same time
+            let _output = self.output.lock().unwrap();
+            self.abandoned.store(true, Ordering::Relaxed);
+        }
+        if finish != Finish::Wait {
+            // keep signalling until the search is over, in case it only just started and cleared the signal
+            while !search.is_finished() {
+                self.engine_stop.store(true, Ordering::Relaxed);
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        let (engine, played) = search.join().unwrap();
+        self.engine = Some(engine);
+        if let (Some(mv), Some(player)) = (played, side_to_move(&self.board)) {
+            let _ = self.board.apply_move(player, mv);
+        }
+    }
+
+    // level <moves per session> <minutes>[:<seconds>] <increment in seconds>
+    // the clock starts at the base time until the GUI says otherwise with time
+    fn set_level(&mut self, argument: &str) -> bool {
+        let fields: Vec<&str> = argument.split_whitespace().collect();
+        if fields.len() != 3 {
+            return false;
+        }
+        let (minutes, seconds) = fields[1].split_once(':').unwrap_or((fields[1], "0"));
+        let base = match (minutes.parse::<u64>(), seconds.parse::<u64>()) {
+            (Ok(minutes), Ok(seconds)) => Duration::from_secs(minutes * 60 + seconds),
+            _ => {
+                return false;
+            }
+        };
+        match (fields[0].parse::<u32>(), parse_seconds(fields[2])) {
+            (Ok(moves), Some(increment)) => {
+                self.moves_per_session = moves;
+                self.increment = increment;
+                self.time_left = Some(base);
+                self.move_time = None;
+                return true;
+            }
+            _ => {
+                return false;
+            }
+        }
+    }
+
+    // start searching if it is the engine's turn
+    fn think(&mut self) {
+        let player = match side_to_move(&self.board) {
+            Some(player) if self.engine_side == Some(player) => player,
+            _ => {
+                return;
+            }
+        };
+
+        // a timed engine searches as deep as its time allows
+        let timed = self.move_time.is_some() || self.time_left.is_some();
+        let default_depth = if timed { engine::MAX_DEPTH } else { engine::SearchLimits::default().depth };
+        let moves_to_go = if self.moves_per_session > 0 {
+            let moves_played = (self.board.get_turn() / 2) as u32;
+            Some(self.moves_per_session - (moves_played % self.moves_per_session))
+        } else {
+            None
+        };
+        let limits = engine::SearchLimits {
+            depth: self.depth.unwrap_or(default_depth),
+            move_time: self.move_time,
+            time_left: if self.move_time.is_some() { None } else { self.time_left },
+            increment: self.increment,
+            moves_to_go,
+            nodes: None,
+        };
+
+        let mut engine = self.engine.take().unwrap();
+        self.engine_stop = engine.stop_signal();
+        self.abandoned.store(false, Ordering::Relaxed);
+        let abandoned = Arc::clone(&self.abandoned);
+        let output = Arc::clone(&self.output);
+        let board = self.board.clone();
+        let post = self.post;
+        self.search = Some(
+            std::thread::spawn(move || {
+                let result = engine.search_with(&board, &limits, |iteration| {
+                    if post {
+                        send(&output, &thinking(&board, iteration));
+                    }
+                });
+
+                let mut output = output.lock().unwrap();
+                let mv = match result.best_move {
+                    Some(mv) if !abandoned.load(Ordering::Relaxed) => mv,
+                    _ => {
+                        return (engine, None);
+                    }
+                };
+                let mut after = board.clone();
+                let _ = after.apply_move(player, mv);
+                let _ = writeln!(output, "move {}", notation::to_uci(&board, mv));
+                // claim the result if the move ended the game
+                match after.get_state() {
+                    game::GameState::Won(game::Player::White) => {
+                        let _ = writeln!(output, "1-0 {{White mates}}");
+                    }
+                    game::GameState::Won(game::Player::Black) => {
+                        let _ = writeln!(output, "0-1 {{Black mates}}");
+                    }
+                    game::GameState::Draw => {
+                        let _ = writeln!(output, "1/2-1/2 {{Stalemate}}");
+                    }
+                    game::GameState::Playing(_) => {}
+                }
+                let _ = output.flush();
+                return (engine, Some(mv));
+            })
+        );
+    }
+}
+
+fn side_to_move(board: &board::Board) -> Option<game::Player> {
+    return match board.get_state() {
+        game::GameState::Playing(p) => Some(*p),
+        _ => None,
+    };
+}
+
+fn parse_seconds(value: &str) -> Option<Duration> {
+    return match value.parse::<f64>() {
+        Ok(seconds) if seconds >= 0.0 && seconds.is_finite() => Some(Duration::from_secs_f64(seconds)),
+        _ => None,
+    };
+}
+
+// <depth> <score in centipawns> <time in centiseconds> <nodes> <line in standard notation>
+// a mate in n moves is scored 100000 + n, and being mated in n moves -100000 - n
+fn thinking(board: &board::Board, result: &engine::SearchResult) -> String {
+    let score = match result.score {
+        engine::Score::Centipawns(cp) => cp,
+        engine::Score::Mate(moves) if moves > 0 => 100000 + moves,
+        engine::Score::Mate(moves) => -100000 + moves,
+    };
+    let mut board = board.clone();
+    let mut line = Vec::new();
+    for &mv in &result.pv {
+        let player = match side_to_move(&board) {
+            Some(player) => player,
+            None => {
+                break;
+            }
+        };
+        line.push(notation::to_san(&board, mv));
+        if board.apply_move(player, mv).is_err() {
+            break;
+        }
+    }
+    return format!(
+        "{} {score} {} {} {}",
+        result.depth,
+        result.elapsed.as_millis() / 10,
+        result.nodes,
+        line.join(" ")
+    );
+}